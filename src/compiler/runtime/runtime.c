@@ -0,0 +1,220 @@
+// Runtime linked into ahead-of-time compiled degu programs.
+// Every function here must match the ones the JIT maps in `compiler::standard`.
+
+#include <math.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// The compiler renames the degu `main` so it does not clash with the C entry point.
+extern void degu_main(void);
+
+void degu_panic(const char *message);
+
+// Prints the shortest digits that round trip without an exponent, `NaN` and `inf`. The same as
+// Rust's `Display` for f64 which the JIT uses.
+void print_number(double value) {
+    if (isnan(value)) {
+        puts("NaN");
+        return;
+    }
+
+    if (isinf(value)) {
+        puts(value < 0 ? "-inf" : "inf");
+        return;
+    }
+
+    // Scientific notation like `-1.25e+21` has the digits and the exponent apart.
+    char scientific[32];
+
+    for (int precision = 0; precision <= 16; ++precision) {
+        snprintf(scientific, sizeof scientific, "%.*e", precision, value);
+        if (strtod(scientific, NULL) == value) {
+            break;
+        }
+    }
+
+    const char *c = scientific;
+    int negative = *c == '-';
+    if (negative) {
+        ++c;
+    }
+
+    char digits[20];
+    int count = 0;
+
+    for (; *c != 'e'; ++c) {
+        if (*c != '.') {
+            digits[count++] = *c;
+        }
+    }
+
+    int exponent = atoi(c + 1);
+
+    while (count > 1 && digits[count - 1] == '0') {
+        --count;
+    }
+
+    // Doubles go from around 1e-324 to 1e308.
+    char buf[400];
+    int length = 0;
+
+    if (negative) {
+        buf[length++] = '-';
+    }
+
+    if (exponent < 0) {
+        buf[length++] = '0';
+        buf[length++] = '.';
+
+        for (int i = -1; i > exponent; --i) {
+            buf[length++] = '0';
+        }
+
+        for (int i = 0; i < count; ++i) {
+            buf[length++] = digits[i];
+        }
+    } else {
+        for (int i = 0; i <= exponent || i < count; ++i) {
+            if (i == exponent + 1) {
+                buf[length++] = '.';
+            }
+
+            buf[length++] = i < count ? digits[i] : '0';
+        }
+    }
+
+    buf[length] = '\0';
+    puts(buf);
+}
+
+void print_int(int64_t value) {
+    printf("%lld\n", (long long) value);
+}
+
+// Encodes a Unicode scalar value as UTF-8 and NUL terminates it, `out` needs 5 bytes.
+static void encode_utf8(uint32_t value, char *out) {
+    if (value < 0x80) {
+        *out++ = (char) value;
+    } else if (value < 0x800) {
+        *out++ = (char) (0xC0 | (value >> 6));
+        *out++ = (char) (0x80 | (value & 0x3F));
+    } else if (value < 0x10000) {
+        *out++ = (char) (0xE0 | (value >> 12));
+        *out++ = (char) (0x80 | ((value >> 6) & 0x3F));
+        *out++ = (char) (0x80 | (value & 0x3F));
+    } else {
+        *out++ = (char) (0xF0 | (value >> 18));
+        *out++ = (char) (0x80 | ((value >> 12) & 0x3F));
+        *out++ = (char) (0x80 | ((value >> 6) & 0x3F));
+        *out++ = (char) (0x80 | (value & 0x3F));
+    }
+
+    *out = '\0';
+}
+
+void print_char(uint32_t value) {
+    char buf[5];
+    encode_utf8(value, buf);
+    puts(buf);
+}
+
+// Exits the program when the value is not a Unicode scalar value.
+uint32_t char_of_int(int64_t value) {
+    if (value < 0 || value > 0x10FFFF || (value >= 0xD800 && value <= 0xDFFF)) {
+        fprintf(stderr, "char_of_int: %lld is not a valid Unicode scalar value\n", (long long) value);
+        exit(1);
+    }
+
+    return (uint32_t) value;
+}
+
+// '\0' results in an empty string because strings are NUL terminated.
+char *string_of_char(uint32_t value) {
+    char *result = malloc(5);
+
+    if (result == NULL) {
+        fputs("Out of memory\n", stderr);
+        exit(1);
+    }
+
+    encode_utf8(value, result);
+    return result;
+}
+
+void print_string(const char *value) {
+    puts(value);
+}
+
+// The result is never freed since there is no memory management yet.
+char *string_concat(const char *a, const char *b) {
+    size_t a_length = strlen(a);
+    size_t b_length = strlen(b);
+    char *result = malloc(a_length + b_length + 1);
+
+    if (result == NULL) {
+        fputs("Out of memory\n", stderr);
+        exit(1);
+    }
+
+    memcpy(result, a, a_length);
+    memcpy(result + a_length, b, b_length + 1);
+    return result;
+}
+
+// Length in Unicode scalar values, UTF-8 continuation bytes are not counted.
+int64_t string_length(const char *value) {
+    int64_t length = 0;
+
+    for (; *value != '\0'; ++value) {
+        if (((unsigned char) *value & 0xC0) != 0x80) {
+            ++length;
+        }
+    }
+
+    return length;
+}
+
+// Exits the program unless the string holds exactly one Unicode scalar value.
+uint32_t char_of_string(const char *value) {
+    if (string_length(value) != 1) {
+        degu_panic("char_of_string: the string must hold exactly one character");
+    }
+
+    // Strings are valid UTF-8 so the lead byte tells the length of the sequence.
+    const unsigned char *bytes = (const unsigned char *) value;
+
+    if (bytes[0] < 0x80) {
+        return bytes[0];
+    } else if (bytes[0] < 0xE0) {
+        return ((uint32_t) (bytes[0] & 0x1F) << 6) | (bytes[1] & 0x3F);
+    } else if (bytes[0] < 0xF0) {
+        return ((uint32_t) (bytes[0] & 0x0F) << 12)
+            | ((uint32_t) (bytes[1] & 0x3F) << 6)
+            | (bytes[2] & 0x3F);
+    } else {
+        return ((uint32_t) (bytes[0] & 0x07) << 18)
+            | ((uint32_t) (bytes[1] & 0x3F) << 12)
+            | ((uint32_t) (bytes[2] & 0x3F) << 6)
+            | (bytes[3] & 0x3F);
+    }
+}
+
+// Compares the UTF-8 bytes which is the same as comparing the scalar values.
+int64_t string_compare(const char *a, const char *b) {
+    int order = strcmp(a, b);
+    return (order > 0) - (order < 0);
+}
+
+// Reports an error the program cannot recover from and exits, like dividing by zero or taking
+// the head of an empty list.
+void degu_panic(const char *message) {
+    fprintf(stderr, "panic: %s\n", message);
+    exit(1);
+}
+
+int main(void) {
+    degu_main();
+    return 0;
+}
@@ -0,0 +1,296 @@
+use llvm_wrap::transformation::OptLevel;
+
+use std::path::Path;
+
+pub const USAGE: &str = "\
+Usage: compiler [COMMAND] [OPTIONS] [--] <FILE>...
+
+Commands:
+    run         Compile and run each file with the JIT (default)
+    check       Only check that each file compiles
+    build       Compile each file to the formats given with --emit (default exe)
+
+Options:
+    --emit=KINDS    Comma separated list of ast|ir|bc|asm|obj|exe
+    -o PATH         Output path, only valid with a single input and emit kind.
+                    Use `-` to write textual output to stdout
+    -O0 .. -O3      Optimization level (default -O1)
+    -q, --quiet     Do not print anything besides the program output and errors
+    -h, --help      Print this message
+    --              Treat the rest of the arguments as files, even ones named like a command
+";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    Run,
+    Check,
+    Build,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Emit {
+    Ast,
+    Ir,
+    Bitcode,
+    Assembly,
+    Object,
+    Executable,
+}
+
+impl Emit {
+    fn parse(kind: &str) -> Result<Self, String> {
+        match kind {
+            "ast" => Ok(Emit::Ast),
+            "ir" => Ok(Emit::Ir),
+            "bc" => Ok(Emit::Bitcode),
+            "asm" => Ok(Emit::Assembly),
+            "obj" => Ok(Emit::Object),
+            "exe" => Ok(Emit::Executable),
+            _ => Err(format!("Unknown emit kind {:?}, expected ast|ir|bc|asm|obj|exe", kind)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Emit::Ast => "ast",
+            Emit::Ir => "ll",
+            Emit::Bitcode => "bc",
+            Emit::Assembly => "s",
+            Emit::Object => "o",
+            Emit::Executable => if cfg!(windows) { "exe" } else { "" },
+        }
+    }
+
+    /// Whether the output can be written to stdout.
+    pub fn is_textual(self) -> bool {
+        match self {
+            Emit::Ast | Emit::Ir | Emit::Assembly => true,
+            Emit::Bitcode | Emit::Object | Emit::Executable => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub command: Command,
+    pub inputs: Vec<String>,
+    pub emit: Vec<Emit>,
+    pub output: Option<String>,
+    pub opt_level: OptLevel,
+    pub quiet: bool,
+    pub help: bool,
+}
+
+impl Options {
+    /// Output path for an emitted artifact of the given input.
+    pub fn output_path(&self, input: &str, emit: Emit) -> String {
+        if let Some(ref output) = self.output {
+            return output.clone();
+        }
+
+        Path::new(input)
+            .with_extension(emit.extension())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+pub fn parse_args<I>(args: I) -> Result<Options, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = Options {
+        command: Command::Run,
+        inputs: Vec::new(),
+        emit: Vec::new(),
+        output: None,
+        opt_level: OptLevel::O1,
+        quiet: false,
+        help: false,
+    };
+
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().map(String::as_str) {
+        Some("run") => Some(Command::Run),
+        Some("check") => Some(Command::Check),
+        Some("build") => Some(Command::Build),
+        _ => None,
+    };
+
+    // Files named like a command come after `--`.
+    if let Some(command) = command {
+        args.next();
+        options.command = command;
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => options.inputs.extend(args.by_ref()),
+            "-h" | "--help" => options.help = true,
+            "-q" | "--quiet" => options.quiet = true,
+            "-O0" => options.opt_level = OptLevel::O0,
+            "-O1" => options.opt_level = OptLevel::O1,
+            "-O2" => options.opt_level = OptLevel::O2,
+            "-O3" => options.opt_level = OptLevel::O3,
+            "-o" => {
+                let output = args.next().ok_or("Missing path after -o")?;
+                options.output = Some(output);
+            },
+            "--emit" => {
+                let kinds = args.next().ok_or("Missing kinds after --emit")?;
+                parse_emit(&mut options, &kinds)?;
+            },
+            _ if arg.starts_with("--emit=") => {
+                parse_emit(&mut options, &arg["--emit=".len()..])?;
+            },
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("Unknown option {:?}", arg));
+            },
+            _ => options.inputs.push(arg),
+        }
+    }
+
+    if options.help {
+        return Ok(options);
+    }
+
+    if options.inputs.is_empty() {
+        return Err("No input files".to_string());
+    }
+
+    if options.command == Command::Build && options.emit.is_empty() {
+        options.emit.push(Emit::Executable);
+    }
+
+    if options.output.is_some() && options.emit.is_empty() {
+        return Err("-o needs an output, use it with build or --emit".to_string());
+    }
+
+    if options.output.is_some() && (options.inputs.len() > 1 || options.emit.len() > 1) {
+        return Err("-o can only be used with a single input file and emit kind".to_string());
+    }
+
+    if options.output.as_ref().map_or(false, |output| output == "-")
+        && !options.emit.iter().all(|emit| emit.is_textual())
+    {
+        return Err("Only textual output can be written to stdout".to_string());
+    }
+
+    Ok(options)
+}
+
+fn parse_emit(options: &mut Options, kinds: &str) -> Result<(), String> {
+    for kind in kinds.split(',') {
+        let emit = Emit::parse(kind)?;
+        if !options.emit.contains(&emit) {
+            options.emit.push(emit);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn defaults_to_run() {
+        let options = parse(&["main.dg"]).unwrap();
+
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(options.inputs, ["main.dg"]);
+        assert!(options.emit.is_empty());
+        assert_eq!(options.output, None);
+        assert_eq!(options.opt_level, OptLevel::O1);
+        assert!(!options.quiet);
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse(&["run", "main.dg"]).unwrap().command, Command::Run);
+        assert_eq!(parse(&["check", "main.dg"]).unwrap().command, Command::Check);
+        assert_eq!(parse(&["build", "main.dg"]).unwrap().command, Command::Build);
+    }
+
+    #[test]
+    fn build_emits_an_executable_by_default() {
+        let options = parse(&["build", "main.dg"]).unwrap();
+        assert_eq!(options.emit, [Emit::Executable]);
+    }
+
+    #[test]
+    fn files_named_like_commands() {
+        let options = parse(&["--", "build"]).unwrap();
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(options.inputs, ["build"]);
+
+        let options = parse(&["check", "--", "run"]).unwrap();
+        assert_eq!(options.command, Command::Check);
+        assert_eq!(options.inputs, ["run"]);
+
+        let options = parse(&["--", "check", "-q"]).unwrap();
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(options.inputs, ["check", "-q"]);
+        assert!(!options.quiet);
+    }
+
+    #[test]
+    fn options() {
+        let options = parse(&["build", "-q", "-O3", "--emit=ir,obj", "a.dg", "b.dg"]).unwrap();
+
+        assert!(options.quiet);
+        assert_eq!(options.opt_level, OptLevel::O3);
+        assert_eq!(options.emit, [Emit::Ir, Emit::Object]);
+        assert_eq!(options.inputs, ["a.dg", "b.dg"]);
+    }
+
+    #[test]
+    fn emit_kinds_are_deduplicated() {
+        let options = parse(&["build", "--emit", "ir", "--emit=ir,bc", "main.dg"]).unwrap();
+        assert_eq!(options.emit, [Emit::Ir, Emit::Bitcode]);
+    }
+
+    #[test]
+    fn output_path() {
+        let options = parse(&["build", "--emit=ir", "main.dg"]).unwrap();
+        assert_eq!(options.output_path("main.dg", Emit::Ir), "main.ll");
+
+        let options = parse(&["build", "--emit=ir", "-o", "out.ll", "main.dg"]).unwrap();
+        assert_eq!(options.output_path("main.dg", Emit::Ir), "out.ll");
+    }
+
+    #[test]
+    fn stdin_is_an_input() {
+        assert_eq!(parse(&["-"]).unwrap().inputs, ["-"]);
+    }
+
+    #[test]
+    fn help_needs_no_input() {
+        assert!(parse(&["--help"]).unwrap().help);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["run"]).is_err());
+        assert!(parse(&["build"]).is_err());
+        assert!(parse(&["build", "-q"]).is_err());
+        assert!(parse(&["--unknown", "main.dg"]).is_err());
+        assert!(parse(&["--emit=wasm", "main.dg"]).is_err());
+        assert!(parse(&["main.dg", "-o"]).is_err());
+        assert!(parse(&["main.dg", "-o", "out"]).is_err());
+        assert!(parse(&["run", "-o", "out", "main.dg"]).is_err());
+        assert!(parse(&["check", "-o", "out", "main.dg"]).is_err());
+        assert!(parse(&["check", "--emit=ir", "-o", "out.ll", "main.dg"]).is_ok());
+        assert!(parse(&["build", "-o", "out", "main.dg"]).is_ok());
+        assert!(parse(&["build", "-o", "out", "a.dg", "b.dg"]).is_err());
+        assert!(parse(&["build", "--emit=ir,bc", "-o", "out", "main.dg"]).is_err());
+        assert!(parse(&["build", "--emit=obj", "-o", "-", "main.dg"]).is_err());
+    }
+}
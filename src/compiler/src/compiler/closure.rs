@@ -0,0 +1,361 @@
+//! Closure conversion.
+//!
+//! A closure is an `i8*` to its environment `{ i8* code, captures... }`. The code is a function
+//! taking the environment and a single argument, so functions of more parameters are closures
+//! returning closures. Environments with captures are allocated on the heap and never freed
+//! since there is no memory management yet.
+//!
+//! Top-level functions are called directly when given enough arguments. Used as values they are
+//! wrapped in closures which collect the arguments and then make the direct call.
+
+use lexpar::lexer::Span;
+
+use llvm_wrap::prelude::*;
+
+use syntax::parser::ast::{Ast, AstNode, Param};
+
+use std::collections::BTreeSet;
+use std::iter;
+
+use crate::typeck;
+
+use super::{CodegenResult, Compiler, CompilerError};
+
+/// What a closure evaluates once it has all of its arguments.
+#[derive(Clone, Copy)]
+enum ClosureBody<'a> {
+    Expr(&'a AstNode),
+    /// Direct call of a top-level function with the arguments.
+    Direct(&'a str),
+}
+
+/// Name of the `index`th parameter of a closure wrapping a top-level function.
+/// It cannot clash with the identifiers in the program.
+fn direct_param(index: usize) -> String {
+    format!("${}", index)
+}
+
+/// Closures only need the names of the parameters, their types are known from the checker.
+fn param_names(params: &[Param]) -> Vec<String> {
+    params.iter().map(|param| param.name.clone()).collect()
+}
+
+impl Compiler {
+    /// Type every closure value has.
+    pub(super) fn closure_type(&self) -> Type {
+        Context::pointer_type(self.context.i8_type())
+    }
+
+    fn closure_code_type(&self, arg: &typeck::Type, ret: &typeck::Type) -> Type {
+        Context::function_type(
+            self.llvm_type(ret),
+            &[self.closure_type(), self.llvm_type(arg)],
+            false)
+    }
+
+    /// Functions defined inside of other functions are closures bound to their name.
+    pub(super) fn build_local_function(
+        &mut self,
+        name: &String,
+        args: &Vec<Param>,
+        body: &AstNode,
+        ty: &typeck::Type) -> CodegenResult
+    {
+        let params = param_names(args);
+        let closure = self.build_closure(Some(name), &params, ClosureBody::Expr(body), ty)?;
+        self.env.bind(name.clone(), closure.clone());
+        Ok(closure)
+    }
+
+    pub(super) fn build_lambda(
+        &mut self,
+        params: &Vec<Param>,
+        body: &AstNode,
+        ty: &typeck::Type) -> CodegenResult
+    {
+        self.build_closure(None, &param_names(params), ClosureBody::Expr(body), ty)
+    }
+
+    /// The closure of a top-level function, used when it is not called directly.
+    pub(super) fn build_function_value(&mut self, span: &Span, name: &String) -> CodegenResult {
+        if let Some(value) = self.function_values.get(name) {
+            return Ok(value.clone());
+        }
+
+        if name == "main" {
+            return Err(CompilerError {
+                message: "The main function cannot be used as a value".to_string(),
+                span: Some(span.clone()),
+            });
+        }
+
+        let arity = self.env.defs[name].params().len();
+        let ty = self.function_type(name);
+        let params = (0..arity).map(direct_param).collect::<Vec<_>>();
+
+        // It captures nothing so it is a constant which can be reused everywhere.
+        let value = self.build_closure(Some(name), &params, ClosureBody::Direct(name), &ty)?;
+        self.function_values.insert(name.clone(), value.clone());
+
+        Ok(value)
+    }
+
+    /// Builds the closure of the function `fn params -> body` of type `ty`.
+    /// Named closures can refer to themselves.
+    fn build_closure(
+        &mut self,
+        name: Option<&String>,
+        params: &[String],
+        body: ClosureBody,
+        ty: &typeck::Type) -> CodegenResult
+    {
+        let (param, rest) = params.split_first().expect("Closures must take an argument.");
+
+        // Locals the body refers to are copied to the environment. Globals and functions are
+        // accessible from anywhere.
+        let mut names = BTreeSet::new();
+        match body {
+            ClosureBody::Expr(body) => referenced_names(body, &mut names),
+            ClosureBody::Direct(_) => names.extend(params.iter().cloned()),
+        }
+
+        let captures = names
+            .into_iter()
+            .filter(|capture| Some(capture) != name && capture != param)
+            .filter_map(|capture| {
+                let value = self.env.lookup_local(&capture).cloned();
+                value.map(|value| (capture, value))
+            })
+            .collect::<Vec<_>>();
+
+        let env_type = {
+            let fields = iter::once(self.closure_type())
+                .chain(captures.iter().map(|(_, value)| value.get_type()))
+                .collect::<Vec<_>>();
+            self.context.struct_type(&fields, false)
+        };
+
+        let code = self.build_closure_code(name, param, rest, body, ty, env_type, &captures)?;
+        let code = self.builder.build_bitcast(code.as_value(), self.closure_type(), None);
+
+        if captures.is_empty() {
+            // Without captures all values of the closure can share a constant environment.
+            let env = self.context.const_struct(&[code], false);
+            let env = self.module.add_global_constant(&env, Some(self.pool.intern("closure")));
+            return Ok(self.builder.build_bitcast(&env, self.closure_type(), None));
+        }
+
+        let env = self.builder.build_malloc(env_type, Some(self.pool.intern("env")));
+
+        let field = self.builder.build_struct_gep(&env, 0, None);
+        self.builder.build_store(&code, &field);
+
+        for (index, (_, value)) in captures.iter().enumerate() {
+            let field = self.builder.build_struct_gep(&env, index as u32 + 1, None);
+            self.builder.build_store(value, &field);
+        }
+
+        Ok(self.builder.build_bitcast(&env, self.closure_type(), Some(self.pool.intern("closure"))))
+    }
+
+    fn build_closure_code(
+        &mut self,
+        name: Option<&String>,
+        param: &String,
+        rest: &[String],
+        body: ClosureBody,
+        ty: &typeck::Type,
+        env_type: Type,
+        captures: &[(String, AnyValue)]) -> Result<Function, CompilerError>
+    {
+        let (arg_type, ret_type) = match ty {
+            typeck::Type::Fn(arg, ret) => (&**arg, &**ret),
+            _ => unreachable!("Closures must be of a function type, found {}.", ty),
+        };
+
+        // `$` cannot appear in identifiers so the name does not clash with any function.
+        let code_name = format!("{}$closure", name.map_or("lambda", String::as_str));
+        let code_type = self.closure_code_type(arg_type, ret_type);
+        let mut code = self.module.function_prototype(Some(self.pool.intern(&code_name)), code_type);
+
+        // Top-level closures are built outside of any function.
+        let insert_block = if self.env.is_top_level() {
+            None
+        } else {
+            Some(self.builder.get_insert_block())
+        };
+
+        let bb = BasicBlock::new(self.pool.intern("entry"), &mut code);
+        self.builder.position_at_end(&bb);
+
+        // The locals of the enclosing function are not accessible from this one.
+        let locals = self.env.enter_function();
+        self.env.push_scope();
+
+        let params = code.params();
+        let env = self.builder.build_bitcast(
+            &params[0],
+            Context::pointer_type(env_type),
+            Some(self.pool.intern("env")));
+
+        for (index, (capture, _)) in captures.iter().enumerate() {
+            let field = self.builder.build_struct_gep(&env, index as u32 + 1, None);
+            let value = self.builder.build_load(&field, Some(self.pool.intern(capture.as_ref())));
+            self.env.bind(capture.clone(), value);
+        }
+
+        // The environment is the closure itself.
+        if let Some(name) = name {
+            self.env.bind(name.clone(), params[0].clone());
+        }
+
+        if param != "_" {
+            let mut arg = params[1].clone();
+            arg.set_name(self.pool.intern(param.as_ref()));
+            self.env.bind(param.clone(), arg);
+        }
+
+        let ret = match body {
+            _ if !rest.is_empty() => self.build_closure(None, rest, body, ret_type),
+            ClosureBody::Expr(body) => self.codegen(body),
+            ClosureBody::Direct(function) => self.build_direct_body(function, ret_type),
+        };
+
+        if let Ok(ref ret) = ret {
+            self.builder.build_ret(ret);
+        }
+
+        self.env.pop_scope();
+        self.env.leave_function(locals);
+
+        match insert_block {
+            Some(block) => self.builder.position_at_end(&block),
+            None => self.builder.clear_insertion_position(),
+        }
+
+        ret?;
+        self.finish_function(&mut code)?;
+
+        Ok(code)
+    }
+
+    /// Calls the top-level function with the parameters bound by the closures wrapping it.
+    fn build_direct_body(&mut self, function: &str, ret_type: &typeck::Type) -> CodegenResult {
+        let f = self.env.defs[function].clone();
+
+        let args = (0..f.params().len())
+            .map(|index| self.env.lookup(&direct_param(index)).cloned())
+            .collect::<Option<Vec<_>>>()
+            .expect("The closure must bind all the arguments.");
+
+        let value = self.builder.build_call(&f, &args, None)
+            .map_err(|err| CompilerError { message: format!("{:?}", err), span: None })?;
+
+        // Builtins return void instead of unit.
+        if *ret_type == typeck::Type::Unit {
+            Ok(self.unit_value())
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Applies a closure of type `ty` to the arguments one at a time.
+    pub(super) fn build_closure_call(
+        &mut self,
+        closure: AnyValue,
+        ty: &typeck::Type,
+        args: &[AnyValue]) -> AnyValue
+    {
+        let mut closure = closure;
+        let mut ty = ty.clone();
+
+        for arg in args {
+            let (arg_type, ret_type) = match ty {
+                typeck::Type::Fn(arg, ret) => (*arg, *ret),
+                _ => unreachable!("Only functions can be applied, found {}.", ty),
+            };
+
+            // The code pointer is the first field of the environment.
+            let code_type = Context::pointer_type(self.closure_code_type(&arg_type, &ret_type));
+            let field = self.builder.build_bitcast(
+                &closure,
+                Context::pointer_type(self.closure_type()),
+                None);
+            let code = self.builder.build_load(&field, Some(self.pool.intern("code")));
+            let code = self.builder.build_bitcast(&code, code_type, None);
+
+            closure = self.builder.build_indirect_call(
+                &code,
+                &[closure.clone(), arg.clone()],
+                Some(self.pool.intern("calltmp")));
+            ty = ret_type;
+        }
+
+        closure
+    }
+}
+
+/// Collects the names an expression refers to, including the ones bound inside of it.
+fn referenced_names(ast: &AstNode, names: &mut BTreeSet<String>) {
+    match &*ast.expr {
+        Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Char(_) | Ast::Str(_) => {},
+        Ast::Type { .. } | Ast::Error => {},
+        Ast::Ref(name) => {
+            names.insert(name.clone());
+        },
+        Ast::Tuple(exprs) | Ast::List(exprs) | Ast::Block(exprs) => {
+            for expr in exprs {
+                referenced_names(expr, names);
+            }
+        },
+        Ast::Function { body, .. } | Ast::Lambda { body, .. } => referenced_names(body, names),
+        Ast::Call { name, args } => {
+            names.insert(name.clone());
+            for arg in args {
+                referenced_names(arg, names);
+            }
+        },
+        Ast::Apply { callee, args } => {
+            referenced_names(callee, names);
+            for arg in args {
+                referenced_names(arg, names);
+            }
+        },
+        Ast::Variable { expr, .. } | Ast::Unary { expr, .. } | Ast::Field { expr, .. } => {
+            referenced_names(expr, names)
+        },
+        Ast::Record { fields } => {
+            for (_, value) in fields {
+                referenced_names(value, names);
+            }
+        },
+        Ast::Update { record, fields } => {
+            referenced_names(record, names);
+            for (_, value) in fields {
+                referenced_names(value, names);
+            }
+        },
+        Ast::BinOp { lhs, rhs, .. } => {
+            referenced_names(lhs, names);
+            referenced_names(rhs, names);
+        },
+        Ast::If { condition, then, el } => {
+            referenced_names(condition, names);
+            referenced_names(then, names);
+            if let Some(el) = el {
+                referenced_names(el, names);
+            }
+        },
+        Ast::Match { expr, arms } => {
+            referenced_names(expr, names);
+            for arm in arms {
+                referenced_names(&arm.body, names);
+            }
+        },
+        Ast::For { start, end, body, .. } => {
+            referenced_names(start, names);
+            referenced_names(end, names);
+            referenced_names(body, names);
+        },
+    }
+}
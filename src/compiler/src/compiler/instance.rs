@@ -0,0 +1,106 @@
+//! Monomorphization.
+//!
+//! Polymorphic top-level functions have no code of their own. Each use resolves to an instance
+//! built for the types it is used with, named after the function and the types like `id$int`.
+//! While an instance is built the types of its body have the variables of the scheme replaced.
+
+use llvm_wrap::prelude::*;
+
+use syntax::parser::ast::AstNode;
+
+use std::mem;
+
+use crate::typeck;
+
+use super::{Compiler, CompilerError, symbol_name};
+
+/// Name of the instance of a polymorphic function where its type variables are `types`.
+/// `$` cannot appear in identifiers so instances do not clash with other functions.
+pub(super) fn instance_name(name: &str, types: &[typeck::Type]) -> String {
+    types
+        .iter()
+        .fold(name.to_string(), |instance, ty| format!("{}${}", instance, ty))
+}
+
+impl Compiler {
+    /// Type of an expression in the instance being built.
+    pub(super) fn node_type(&self, ast: &AstNode) -> typeck::Type {
+        self.types.node(ast).substitute(&self.substitution)
+    }
+
+    /// Types the variables of the polymorphic name the node refers to stand for.
+    pub(super) fn instance_types(&self, ast: &AstNode) -> Option<Vec<typeck::Type>> {
+        self.types.instance(ast).map(|types| {
+            types
+                .iter()
+                .map(|ty| ty.substitute(&self.substitution))
+                .collect()
+        })
+    }
+
+    /// Name of the instance of a polymorphic top-level function where its type variables are
+    /// `types`. The instance is built the first time it is used.
+    pub(super) fn build_function_instance(
+        &mut self,
+        name: &str,
+        types: &[typeck::Type]) -> Result<String, CompilerError>
+    {
+        let instance = instance_name(name, types);
+
+        if self.env.defs.contains_key(&instance) {
+            return Ok(instance);
+        }
+
+        let (args, body) = self.generic_functions[name].clone();
+        let scheme = self.types.scheme(name)
+            .cloned()
+            .expect("Polymorphic functions must have a scheme.");
+
+        let ty = scheme.instantiate(types);
+        let (arg_types, ret_type) = ty.split_function(args.len())
+            .expect("Functions must take all of their parameters.");
+
+        let fn_type = {
+            let arg_types = arg_types.iter().map(|ty| self.llvm_type(ty)).collect::<Vec<_>>();
+            Context::function_type(self.llvm_type(&ret_type), &arg_types, false)
+        };
+        let function = self.module.function_prototype(
+            Some(self.pool.intern(&symbol_name(&instance))),
+            fn_type);
+
+        // Declared before the body is built so it can call itself.
+        self.env.defs.insert(instance.clone(), function);
+        self.instances.insert(instance.clone(), ty);
+
+        let substitution = scheme.vars.iter().cloned().zip(types.iter().cloned()).collect();
+        let substitution = mem::replace(&mut self.substitution, substitution);
+        let recursive = self.recursive_instances.insert(name.to_string(), instance.clone());
+
+        // Instances can be built in the middle of another function or at the top level.
+        let insert_block = if self.env.is_top_level() {
+            None
+        } else {
+            Some(self.builder.get_insert_block())
+        };
+        let locals = self.env.enter_function();
+
+        let result = self.build_function(&instance, &args, &body);
+
+        self.env.leave_function(locals);
+
+        match insert_block {
+            Some(block) => self.builder.position_at_end(&block),
+            None => self.builder.clear_insertion_position(),
+        }
+
+        self.substitution = substitution;
+        match recursive {
+            Some(outer) => self.recursive_instances.insert(name.to_string(), outer),
+            None => self.recursive_instances.remove(name),
+        };
+
+        result?;
+
+        Ok(instance)
+    }
+}
@@ -0,0 +1,78 @@
+use llvm_wrap::execution_engine::CodeGenFileType;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use super::{Compiler, CompilerError};
+
+const RUNTIME_SOURCE: &str = include_str!("../../runtime/runtime.c");
+
+/// Name the degu `main` is exported as in native executables.
+const ENTRY_NAME: &str = "degu_main";
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("degu-{}-{}", std::process::id(), name))
+}
+
+impl Compiler {
+    /// Compiles the module to an object file and links it with the runtime into an executable.
+    ///
+    /// The system C compiler is used as a linker driver. It can be overridden with `CC`.
+    pub fn emit_executable(&mut self, path: &str) -> Result<(), CompilerError> {
+        let mut main = self.module.get_function(self.pool.intern("main"))
+            .ok_or(CompilerError {
+                message: "Cannot build an executable without a main function".to_string(),
+                span: None,
+            })?;
+
+        let object_path = temp_path("main.o");
+        let runtime_path = temp_path("runtime.c");
+
+        // The C runtime provides the real entry point and calls back into the degu main.
+        main.set_name(self.pool.intern(ENTRY_NAME));
+        let emitted = self.emit_native(&object_path.to_string_lossy(), CodeGenFileType::Object);
+        main.set_name(self.pool.intern("main"));
+        emitted?;
+
+        let result = fs::write(&runtime_path, RUNTIME_SOURCE)
+            .map_err(|err| CompilerError {
+                message: format!("Cannot write the runtime to {:?}: {}", runtime_path, err),
+                span: None,
+            })
+            .and_then(|_| link(&[&object_path, &runtime_path], path));
+
+        let _ = fs::remove_file(&object_path);
+        let _ = fs::remove_file(&runtime_path);
+
+        result
+    }
+}
+
+fn link(inputs: &[&PathBuf], output: &str) -> Result<(), CompilerError> {
+    let linker = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let mut command = Command::new(&linker);
+    command.args(inputs).arg("-o").arg(output);
+
+    if cfg!(unix) {
+        // LLVM can lower floating point operations to libm calls.
+        command.arg("-lm");
+    }
+
+    let status = command.status()
+        .map_err(|err| CompilerError {
+            message: format!("Cannot run the linker {:?}: {}", linker, err),
+            span: None,
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(CompilerError {
+            message: format!("Linking {} failed with {}", output, status),
+            span: None,
+        })
+    }
+}
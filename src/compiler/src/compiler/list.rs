@@ -0,0 +1,273 @@
+//! Immutable singly linked lists.
+//!
+//! A list is an `i8*` to its first cell `{ item, i8* tail }` and the empty list is null. Cells
+//! are allocated on the heap and never freed, like closure environments. Lists built at the top
+//! level have constant items so their cells are constant globals instead.
+//!
+//! The list functions of the standard library are polymorphic so they are generated for every
+//! item type they are used with. Their bodies are built here.
+
+use llvm_wrap::builder::IntPredicate;
+use llvm_wrap::prelude::*;
+
+use syntax::parser::ast::{AstNode, BinOpKind};
+
+use crate::typeck;
+
+use super::{CodegenResult, Compiler};
+
+impl Compiler {
+    /// Type every list value has regardless of its items.
+    pub(super) fn list_type(&self) -> Type {
+        Context::pointer_type(self.context.i8_type())
+    }
+
+    fn cell_type(&self, item: &typeck::Type) -> Type {
+        self.context.struct_type(&[self.llvm_type(item), self.list_type()], false)
+    }
+
+    fn empty_list(&mut self) -> AnyValue {
+        let list_type = self.list_type();
+        self.builder.build_const_null(list_type)
+    }
+
+    pub(super) fn build_list(&mut self, ty: &typeck::Type, items: &Vec<AstNode>) -> CodegenResult {
+        let item_type = match ty {
+            typeck::Type::List(item) => &**item,
+            _ => unreachable!("List literals must be of a list type, found {}.", ty),
+        };
+
+        let values = items
+            .iter()
+            .map(|item| self.codegen(item))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut list = self.empty_list();
+        for value in values.iter().rev() {
+            list = self.build_cons(item_type, value, &list);
+        }
+
+        Ok(list)
+    }
+
+    pub(super) fn build_list_binop(
+        &mut self,
+        kind: BinOpKind,
+        lhs: &AstNode,
+        rhs: &AstNode) -> CodegenResult
+    {
+        // The right operand is a list in both cases.
+        let ty = self.node_type(rhs);
+
+        let lhs = self.codegen(lhs)?;
+        let rhs = self.codegen(rhs)?;
+
+        let item_type = match ty {
+            typeck::Type::List(item) => *item,
+            _ => unreachable!("Expected a list operand, found {}.", ty),
+        };
+
+        match kind {
+            BinOpKind::Cons => Ok(self.build_cons(&item_type, &lhs, &rhs)),
+            BinOpKind::Append => {
+                let append = self.build_generic_instance("append", &[item_type])?;
+                let f = self.env.defs[&append].clone();
+
+                Ok(self.builder.build_call(&f, &[lhs, rhs], Some(self.pool.intern("appendtmp")))
+                    .expect("Append must be called with both lists."))
+            },
+            _ => unreachable!("{:?} is not a list operation.", kind),
+        }
+    }
+
+    /// Prepends `head` to `tail`.
+    fn build_cons(&mut self, item: &typeck::Type, head: &AnyValue, tail: &AnyValue) -> AnyValue {
+        let list_type = self.list_type();
+
+        // Top-level lists are constant folded.
+        if self.env.is_top_level() {
+            let cell = self.context.const_struct(&[head.clone(), tail.clone()], false);
+            let cell = self.module.add_global_constant(&cell, Some(self.pool.intern("cell")));
+            return self.builder.build_bitcast(&cell, list_type, None);
+        }
+
+        let cell = self.builder.build_malloc(self.cell_type(item), Some(self.pool.intern("cell")));
+
+        let field = self.builder.build_struct_gep(&cell, 0, None);
+        self.builder.build_store(head, &field);
+        let field = self.builder.build_struct_gep(&cell, 1, None);
+        self.builder.build_store(tail, &field);
+
+        self.builder.build_bitcast(&cell, list_type, Some(self.pool.intern("list")))
+    }
+
+    /// Loads the head and the tail of a list that is not empty.
+    pub(super) fn build_uncons(&mut self, item: &typeck::Type, list: &AnyValue) -> (AnyValue, AnyValue) {
+        let cell_type = Context::pointer_type(self.cell_type(item));
+        let cell = self.builder.build_bitcast(list, cell_type, None);
+
+        let field = self.builder.build_struct_gep(&cell, 0, None);
+        let head = self.builder.build_load(&field, Some(self.pool.intern("head")));
+        let field = self.builder.build_struct_gep(&cell, 1, None);
+        let tail = self.builder.build_load(&field, Some(self.pool.intern("tail")));
+
+        (head, tail)
+    }
+
+    /// Branches on whether the list is empty. Returns the block for the empty list and the one
+    /// for a cell, both still empty.
+    pub(super) fn build_list_branch(&mut self, list: &AnyValue) -> (BasicBlock, BasicBlock) {
+        let mut f = self.builder.get_insert_block().parent();
+
+        let empty_block = BasicBlock::new(self.pool.intern("empty"), &mut f);
+        let cell_block = BasicBlock::new(self.pool.intern("cell"), &mut f);
+
+        let empty = self.empty_list();
+        let is_empty = self.builder.build_int_cmp(
+            IntPredicate::EQ,
+            list,
+            &empty,
+            Some(self.pool.intern("isempty")));
+        self.builder.build_conditional_branch(&is_empty, &empty_block, &cell_block);
+
+        (empty_block, cell_block)
+    }
+
+    /// Calls the function being built with other arguments.
+    fn build_recursive_call(&mut self, args: &[AnyValue]) -> AnyValue {
+        let f = self.builder.get_insert_block().parent();
+
+        self.builder.build_call(&f, args, None)
+            .expect("Recursive calls must pass all of the arguments.")
+    }
+
+    /// `map f list` is the list of `f` applied to every item.
+    pub(super) fn build_list_map(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let (f, list) = (&params[0], &params[1]);
+        let (item, result) = (&types[0], &types[1]);
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        let empty = self.empty_list();
+        self.builder.build_ret(&empty);
+
+        self.builder.position_at_end(&cell_block);
+        let (head, tail) = self.build_uncons(item, list);
+        let f_type = typeck::Type::function(vec![item.clone()], result.clone());
+        let mapped = self.build_closure_call(f.clone(), &f_type, &[head]);
+        let rest = self.build_recursive_call(&[f.clone(), tail]);
+        let cell = self.build_cons(result, &mapped, &rest);
+        self.builder.build_ret(&cell);
+    }
+
+    /// `filter f list` is the list of the items `f` returns true for.
+    pub(super) fn build_list_filter(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let (f, list) = (&params[0], &params[1]);
+        let item = &types[0];
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        self.builder.build_ret(list);
+
+        self.builder.position_at_end(&cell_block);
+        let (head, tail) = self.build_uncons(item, list);
+        let f_type = typeck::Type::function(vec![item.clone()], typeck::Type::Bool);
+        let keep = self.build_closure_call(f.clone(), &f_type, &[head.clone()]);
+        let rest = self.build_recursive_call(&[f.clone(), tail]);
+
+        let mut function = self.builder.get_insert_block().parent();
+        let keep_block = BasicBlock::new(self.pool.intern("keep"), &mut function);
+        let skip_block = BasicBlock::new(self.pool.intern("skip"), &mut function);
+        self.builder.build_conditional_branch(&keep, &keep_block, &skip_block);
+
+        self.builder.position_at_end(&keep_block);
+        let cell = self.build_cons(item, &head, &rest);
+        self.builder.build_ret(&cell);
+
+        self.builder.position_at_end(&skip_block);
+        self.builder.build_ret(&rest);
+    }
+
+    /// `fold f state list` passes the state through `f` with every item from the first one.
+    pub(super) fn build_list_fold(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let (f, state, list) = (&params[0], &params[1], &params[2]);
+        let (item, state_type) = (&types[0], &types[1]);
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        self.builder.build_ret(state);
+
+        self.builder.position_at_end(&cell_block);
+        let (head, tail) = self.build_uncons(item, list);
+        let f_type = typeck::Type::function(
+            vec![state_type.clone(), item.clone()],
+            state_type.clone());
+        let next = self.build_closure_call(f.clone(), &f_type, &[state.clone(), head]);
+        let result = self.build_recursive_call(&[f.clone(), next, tail]);
+        self.builder.build_ret(&result);
+    }
+
+    pub(super) fn build_list_length(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let list = &params[0];
+        let i64_type = self.context.i64_type();
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        let zero = self.builder.build_const_int(i64_type, 0, true);
+        self.builder.build_ret(&zero);
+
+        self.builder.position_at_end(&cell_block);
+        let (_, tail) = self.build_uncons(&types[0], list);
+        let rest = self.build_recursive_call(&[tail]);
+        let one = self.builder.build_const_int(i64_type, 1, true);
+        let length = self.builder.build_add(&rest, &one, Some(self.pool.intern("length")));
+        self.builder.build_ret(&length);
+    }
+
+    pub(super) fn build_list_head(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let list = &params[0];
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        self.build_panic("head of an empty list");
+
+        self.builder.position_at_end(&cell_block);
+        let (head, _) = self.build_uncons(&types[0], list);
+        self.builder.build_ret(&head);
+    }
+
+    pub(super) fn build_list_tail(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let list = &params[0];
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        self.build_panic("tail of an empty list");
+
+        self.builder.position_at_end(&cell_block);
+        let (_, tail) = self.build_uncons(&types[0], list);
+        self.builder.build_ret(&tail);
+    }
+
+    /// `append a b` copies the cells of `a` in front of `b`.
+    pub(super) fn build_list_append(&mut self, params: &[AnyValue], types: &[typeck::Type]) {
+        let (list, other) = (&params[0], &params[1]);
+        let item = &types[0];
+
+        let (empty_block, cell_block) = self.build_list_branch(list);
+
+        self.builder.position_at_end(&empty_block);
+        self.builder.build_ret(other);
+
+        self.builder.position_at_end(&cell_block);
+        let (head, tail) = self.build_uncons(item, list);
+        let rest = self.build_recursive_call(&[tail, other.clone()]);
+        let cell = self.build_cons(item, &head, &rest);
+        self.builder.build_ret(&cell);
+    }
+}
@@ -0,0 +1,462 @@
+//! Pattern matching.
+//!
+//! The arms of a `match` are compiled to a decision tree which tests each part of the value at
+//! most once on every path. Ints, chars, bools and the tags of unions are tested with a `switch`,
+//! floats and strings with comparisons. The leaves of the tree jump to the block of their arm
+//! which gets the values of the bound names through PHIs when it is reached from several leaves.
+
+use lexpar::lexer::Span;
+
+use llvm_wrap::prelude::*;
+
+use syntax::parser::ast::{AstNode, BinOpKind, Literal, MatchArm, Pattern};
+
+use crate::typeck::{self, TypeDecl};
+
+use super::{CodegenResult, Compiler, pretty_span};
+
+/// Part of a pattern still to be matched.
+#[derive(Clone, Copy)]
+enum Pat<'a> {
+    Pattern(&'a Pattern),
+    /// Items of a list pattern after the ones already matched.
+    Items(&'a [Pattern]),
+    Wildcard,
+}
+
+/// Arm with the patterns of the parts of the value left to test and the names bound so far.
+#[derive(Clone)]
+struct Row<'a> {
+    patterns: Vec<Pat<'a>>,
+    bindings: Vec<(String, AnyValue)>,
+    arm: usize,
+}
+
+/// Block the decision tree reaches an arm from with the values of its names.
+type Leaf = (BasicBlock, Vec<(String, AnyValue)>);
+
+/// Parts of the value being matched with their types.
+type Parts = Vec<(AnyValue, typeck::Type)>;
+
+/// What a part of the value is tested for.
+#[derive(Clone, PartialEq)]
+enum Test {
+    Literal(Literal),
+    Nil,
+    Cons,
+    Case(String),
+}
+
+/// The items of a list pattern.
+fn list_items(pattern: Pat) -> Option<&[Pattern]> {
+    match pattern {
+        Pat::Pattern(Pattern::List(items)) => Some(items),
+        Pat::Items(items) => Some(items),
+        _ => None,
+    }
+}
+
+/// Patterns of the parts a test gives access to when the pattern passes it.
+fn test_parts<'a>(test: &Test, pattern: Pat<'a>) -> Option<Vec<Pat<'a>>> {
+    match test {
+        Test::Literal(expected) => match pattern {
+            Pat::Pattern(Pattern::Literal(literal)) if literal == expected => Some(Vec::new()),
+            _ => None,
+        },
+        Test::Nil => match list_items(pattern) {
+            Some(items) if items.is_empty() => Some(Vec::new()),
+            _ => None,
+        },
+        Test::Cons => match (pattern, list_items(pattern)) {
+            (Pat::Pattern(Pattern::Cons(head, tail)), _) => Some(vec![Pat::Pattern(head), Pat::Pattern(tail)]),
+            (_, Some(items)) if !items.is_empty() => Some(vec![Pat::Pattern(&items[0]), Pat::Items(&items[1..])]),
+            _ => None,
+        },
+        Test::Case(expected) => match pattern {
+            Pat::Pattern(Pattern::Constructor(name, fields)) if name == expected => {
+                Some(fields.iter().map(Pat::Pattern).collect())
+            },
+            Pat::Pattern(Pattern::Ident(name)) if name == expected => Some(Vec::new()),
+            _ => None,
+        },
+    }
+}
+
+/// The parts with the one at `column` replaced by its own parts.
+fn replace_part(parts: &Parts, column: usize, replacement: Parts) -> Parts {
+    parts[..column]
+        .iter()
+        .cloned()
+        .chain(replacement)
+        .chain(parts[column + 1..].iter().cloned())
+        .collect()
+}
+
+impl Compiler {
+    pub(super) fn build_match(
+        &mut self,
+        span: &Span,
+        ty: &typeck::Type,
+        expr: &AstNode,
+        arms: &Vec<MatchArm>) -> CodegenResult
+    {
+        let value_type = self.node_type(expr);
+        let value = self.codegen(expr)?;
+
+        let rows = arms
+            .iter()
+            .enumerate()
+            .map(|(arm, MatchArm { pattern, .. })| Row {
+                patterns: vec![Pat::Pattern(pattern)],
+                bindings: Vec::new(),
+                arm,
+            })
+            .collect();
+
+        let mut leaves = arms.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+        self.build_decision(span, vec![(value, value_type)], rows, &mut leaves);
+
+        // Arms which are never matched have no leaves and are left out.
+        let mut results = Vec::new();
+        for (arm, leaves) in arms.iter().zip(leaves) {
+            if !leaves.is_empty() {
+                let value = self.build_arm(arm, leaves)?;
+                results.push((value, self.builder.get_insert_block()));
+            }
+        }
+
+        let mut f = self.builder.get_insert_block().parent();
+        let merge_block = BasicBlock::new(self.pool.intern("matchmerge"), &mut f);
+
+        for (_, block) in &results {
+            self.builder.position_at_end(block);
+            self.builder.build_branch(&merge_block);
+        }
+
+        self.builder.position_at_end(&merge_block);
+        let mut phi = self.builder.build_phi(
+            self.llvm_type(ty),
+            Some(self.pool.intern("matchtmp")));
+        phi.add_incoming(&results);
+
+        Ok(phi.to_value())
+    }
+
+    /// Builds the body of an arm in a block the leaves jump to.
+    fn build_arm(&mut self, arm: &MatchArm, mut leaves: Vec<Leaf>) -> CodegenResult {
+        let mut f = self.builder.get_insert_block().parent();
+        let arm_block = BasicBlock::new(self.pool.intern("arm"), &mut f);
+
+        // Names can be bound in any order depending on the path to the leaf.
+        for (block, bindings) in &mut leaves {
+            bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+            self.builder.position_at_end(block);
+            self.builder.build_branch(&arm_block);
+        }
+
+        self.builder.position_at_end(&arm_block);
+        self.env.push_scope();
+
+        let (_, names) = &leaves[0];
+        for (index, (name, value)) in names.iter().enumerate() {
+            let value = if leaves.len() == 1 {
+                value.clone()
+            } else {
+                let mut phi = self.builder.build_phi(value.get_type(), Some(self.pool.intern(name.as_ref())));
+                let incoming = leaves
+                    .iter()
+                    .map(|(block, bindings)| (bindings[index].1.clone(), block.clone()))
+                    .collect::<Vec<_>>();
+                phi.add_incoming(&incoming);
+                phi.to_value()
+            };
+
+            self.env.bind(name.clone(), value);
+        }
+
+        let value = self.codegen(&arm.body);
+        self.env.pop_scope();
+
+        value
+    }
+
+    /// Whether the pattern matches anything. Gives the name it binds if there is one.
+    fn binder<'a>(&self, pattern: Pat<'a>) -> Option<Option<&'a String>> {
+        match pattern {
+            Pat::Wildcard | Pat::Pattern(Pattern::Wildcard) => Some(None),
+            Pat::Pattern(Pattern::Ident(name)) if self.types.constructor(name).is_none() => Some(Some(name)),
+            _ => None,
+        }
+    }
+
+    /// Builds the tests telling which of the rows is the first one to match the parts.
+    fn build_decision(&mut self, span: &Span, parts: Parts, rows: Vec<Row>, leaves: &mut [Vec<Leaf>]) {
+        if rows.is_empty() {
+            // The type checker warns about the values reaching this.
+            self.build_panic(&format!("incomplete match at {}", pretty_span(span)));
+            return;
+        }
+
+        // Test the first part the first row does not match regardless of its value.
+        let column = rows[0].patterns
+            .iter()
+            .position(|&pattern| self.binder(pattern).is_none());
+
+        let column = match column {
+            Some(column) => column,
+            None => {
+                let mut row = rows.into_iter().next().unwrap();
+
+                for (&pattern, (value, _)) in row.patterns.iter().zip(&parts) {
+                    if let Some(Some(name)) = self.binder(pattern) {
+                        row.bindings.push((name.clone(), value.clone()));
+                    }
+                }
+
+                leaves[row.arm].push((self.builder.get_insert_block(), row.bindings));
+                return;
+            },
+        };
+
+        let (value, ty) = parts[column].clone();
+
+        match &ty {
+            typeck::Type::Unit | typeck::Type::Tuple(_) => {
+                let items = match &ty {
+                    typeck::Type::Tuple(items) => items.clone(),
+                    _ => Vec::new(),
+                };
+
+                // Tuples always match, only their items are tested.
+                let items = items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, item)| (self.builder.build_extract_value(&value, index as u32, None), item))
+                    .collect::<Vec<_>>();
+
+                let rows = self.specialize(&rows, column, &value, items.len(), |pattern| match pattern {
+                    Pat::Pattern(Pattern::Tuple(items)) => Some(items.iter().map(Pat::Pattern).collect()),
+                    _ => None,
+                });
+
+                self.build_decision(span, replace_part(&parts, column, items), rows, leaves);
+            },
+            typeck::Type::Bool | typeck::Type::Int | typeck::Type::Char => {
+                let tests = self.tests(&rows, column);
+                let constants = tests
+                    .iter()
+                    .map(|test| match test {
+                        Test::Literal(literal) => self.build_literal(literal),
+                        _ => unreachable!("Only literals can be compared with {}.", ty),
+                    })
+                    .collect();
+
+                // Only bools can have all of their values listed.
+                let is_complete = ty == typeck::Type::Bool && tests.len() == 2;
+                let (blocks, default_block) = self.build_switch(&value, constants);
+
+                for (test, block) in tests.iter().zip(blocks) {
+                    self.builder.position_at_end(&block);
+                    let rows = self.specialize(&rows, column, &value, 0, |pattern| test_parts(test, pattern));
+                    self.build_decision(span, replace_part(&parts, column, Vec::new()), rows, leaves);
+                }
+
+                self.builder.position_at_end(&default_block);
+                self.build_default(span, &parts, &rows, column, is_complete, leaves);
+            },
+            typeck::Type::Float | typeck::Type::String => {
+                for test in self.tests(&rows, column) {
+                    let literal = match &test {
+                        Test::Literal(literal) => self.build_literal(literal),
+                        _ => unreachable!("Only literals can be compared with {}.", ty),
+                    };
+
+                    let is_equal = match ty {
+                        typeck::Type::Float => self.build_fp_binop(BinOpKind::Eq, &value, &literal),
+                        _ => self.build_string_binop(BinOpKind::Eq, &value, &literal),
+                    };
+
+                    let mut f = self.builder.get_insert_block().parent();
+                    let equal_block = BasicBlock::new(self.pool.intern("equal"), &mut f);
+                    let next_block = BasicBlock::new(self.pool.intern("next"), &mut f);
+                    self.builder.build_conditional_branch(&is_equal, &equal_block, &next_block);
+
+                    self.builder.position_at_end(&equal_block);
+                    let rows = self.specialize(&rows, column, &value, 0, |pattern| test_parts(&test, pattern));
+                    self.build_decision(span, replace_part(&parts, column, Vec::new()), rows, leaves);
+
+                    self.builder.position_at_end(&next_block);
+                }
+
+                self.build_default(span, &parts, &rows, column, false, leaves);
+            },
+            typeck::Type::List(item) => {
+                let (empty_block, cell_block) = self.build_list_branch(&value);
+
+                self.builder.position_at_end(&empty_block);
+                let empty_rows = self.specialize(&rows, column, &value, 0, |pattern| test_parts(&Test::Nil, pattern));
+                self.build_decision(span, replace_part(&parts, column, Vec::new()), empty_rows, leaves);
+
+                self.builder.position_at_end(&cell_block);
+                let (head, tail) = self.build_uncons(item, &value);
+                let cell_rows = self.specialize(&rows, column, &value, 2, |pattern| test_parts(&Test::Cons, pattern));
+                let cell_parts = vec![(head, (**item).clone()), (tail, ty.clone())];
+                self.build_decision(span, replace_part(&parts, column, cell_parts), cell_rows, leaves);
+            },
+            typeck::Type::Named(union) => {
+                let case_count = match &self.types.decls()[union] {
+                    TypeDecl::Union(cases) => cases.len(),
+                    TypeDecl::Record(_) => unreachable!("Records cannot be matched by constructors."),
+                };
+
+                let tests = self.tests(&rows, column);
+                let constants = tests
+                    .iter()
+                    .map(|test| match test {
+                        Test::Case(case) => {
+                            let (_, tag, _) = self.types.constructor(case)
+                                .expect("Constructors must be checked before they are matched.");
+                            self.builder.build_const_int(self.context.i32_type(), tag as u64, false)
+                        },
+                        _ => unreachable!("Only constructors can be matched with {}.", ty),
+                    })
+                    .collect();
+
+                let tag = self.builder.build_struct_gep(&value, 0, None);
+                let tag = self.builder.build_load(&tag, Some(self.pool.intern("tag")));
+                let (blocks, default_block) = self.build_switch(&tag, constants);
+
+                for (test, block) in tests.iter().zip(blocks) {
+                    self.builder.position_at_end(&block);
+
+                    let fields = match test {
+                        Test::Case(case) => self.build_case_fields(case, &value),
+                        _ => unreachable!(),
+                    };
+
+                    let rows = self.specialize(&rows, column, &value, fields.len(), |pattern| test_parts(test, pattern));
+                    self.build_decision(span, replace_part(&parts, column, fields), rows, leaves);
+                }
+
+                self.builder.position_at_end(&default_block);
+                self.build_default(span, &parts, &rows, column, tests.len() == case_count, leaves);
+            },
+            typeck::Type::Fn(..) | typeck::Type::Var(_) => {
+                unreachable!("Values of type {} can only be matched by names.", ty)
+            },
+        }
+    }
+
+    /// Continues with the rows matching the values none of the tests at `column` is for.
+    fn build_default(
+        &mut self,
+        span: &Span,
+        parts: &Parts,
+        rows: &[Row],
+        column: usize,
+        is_complete: bool,
+        leaves: &mut [Vec<Leaf>])
+    {
+        if is_complete {
+            self.builder.build_unreachable();
+            return;
+        }
+
+        let (value, _) = &parts[column];
+        let rows = self.specialize(rows, column, value, 0, |_| None);
+        self.build_decision(span, replace_part(parts, column, Vec::new()), rows, leaves);
+    }
+
+    /// The rows which still can match once the part at `column` passed a test, with the parts
+    /// the test gives access to in its place. `passes` gives the patterns of those parts when
+    /// the pattern passes the test.
+    fn specialize<'a>(
+        &self,
+        rows: &[Row<'a>],
+        column: usize,
+        value: &AnyValue,
+        arity: usize,
+        passes: impl Fn(Pat<'a>) -> Option<Vec<Pat<'a>>>) -> Vec<Row<'a>>
+    {
+        rows.iter()
+            .filter_map(|row| {
+                let mut row = row.clone();
+                let pattern = row.patterns[column];
+
+                let parts = match self.binder(pattern) {
+                    Some(name) => {
+                        if let Some(name) = name {
+                            row.bindings.push((name.clone(), value.clone()));
+                        }
+                        vec![Pat::Wildcard; arity]
+                    },
+                    None => passes(pattern)?,
+                };
+
+                row.patterns.splice(column..=column, parts);
+                Some(row)
+            })
+            .collect()
+    }
+
+    /// The tests the rows do on the part at `column` in the order of the rows.
+    fn tests(&self, rows: &[Row], column: usize) -> Vec<Test> {
+        let mut tests = Vec::new();
+
+        for row in rows {
+            let pattern = row.patterns[column];
+
+            let test = match (pattern, list_items(pattern)) {
+                (Pat::Pattern(Pattern::Literal(literal)), _) => Test::Literal(literal.clone()),
+                (_, Some(items)) if items.is_empty() => Test::Nil,
+                (_, Some(_)) | (Pat::Pattern(Pattern::Cons(..)), _) => Test::Cons,
+                (Pat::Pattern(Pattern::Constructor(name, _)), _) => Test::Case(name.clone()),
+                (Pat::Pattern(Pattern::Ident(name)), _) if self.binder(pattern).is_none() => {
+                    Test::Case(name.clone())
+                },
+                _ => continue,
+            };
+
+            if !tests.contains(&test) {
+                tests.push(test);
+            }
+        }
+
+        tests
+    }
+
+    /// Switches on an integer with a block for each of the constants.
+    /// Returns the blocks of the constants and the one of the other values.
+    fn build_switch(&mut self, value: &AnyValue, constants: Vec<AnyValue>) -> (Vec<BasicBlock>, BasicBlock) {
+        let mut f = self.builder.get_insert_block().parent();
+
+        let blocks = constants
+            .iter()
+            .map(|_| BasicBlock::new(self.pool.intern("case"), &mut f))
+            .collect::<Vec<_>>();
+        let default_block = BasicBlock::new(self.pool.intern("default"), &mut f);
+
+        let cases = constants.into_iter().zip(blocks.iter().cloned()).collect::<Vec<_>>();
+        self.builder.build_switch(value, &default_block, &cases);
+
+        (blocks, default_block)
+    }
+
+    fn build_literal(&mut self, literal: &Literal) -> AnyValue {
+        match literal {
+            Literal::Integer(value) => {
+                self.builder.build_const_int(self.context.i64_type(), *value as u64, true)
+            },
+            Literal::Float(value) => self.builder.build_const_fp(self.context.f64_type(), *value),
+            Literal::Bool(value) => {
+                self.builder.build_const_int(self.context.i1_type(), *value as u64, false)
+            },
+            Literal::Char(value) => {
+                self.builder.build_const_int(self.context.i32_type(), *value as u64, false)
+            },
+            Literal::Str(value) => {
+                self.module.add_global_string(value.as_bytes(), Some(self.pool.intern("str")))
+            },
+        }
+    }
+}
@@ -1,37 +1,144 @@
 use lexpar::lexer::Span;
 
 use llvm_wrap::analysis::{VerifierFailureAction, verify_function, verify_module};
-use llvm_wrap::builder::RealPredicate;
-use llvm_wrap::execution_engine::initialize_jit;
+use llvm_wrap::builder::{IntPredicate, RealPredicate};
+use llvm_wrap::execution_engine::{
+    CodeGenFileType,
+    TargetMachine,
+    initialize_jit,
+    initialize_native_target,
+};
 use llvm_wrap::intern::CStringInternPool;
 use llvm_wrap::prelude::*;
+use llvm_wrap::transformation::{ModulePassManager, OptLevel};
 
-use syntax::parser::ast::{Ast, AstNode, BinOpKind, Prototype};
+use syntax::diagnostics::{Diagnostic, codes};
+use syntax::parser::ast::{Ast, AstNode, BinOpKind, Param, Pattern, Prototype, UnOpKind};
 
 use std::collections::HashMap;
+use std::fmt;
 
+use crate::typeck::{self, TypeTable, TypeVar};
+
+pub use self::standard::builtins;
+
+use self::instance::instance_name;
+
+mod closure;
+mod instance;
+mod link;
+mod list;
+mod matching;
+mod record;
 mod standard;
+mod union;
 
-fn pretty_span(span: &Span) -> String {
+pub(crate) fn pretty_span(span: &Span) -> String {
     format!("{}:{}..{}", span.line, span.lo, span.hi)
 }
 
+/// Name of the LLVM function of a top-level function of the program.
+///
+/// `.` cannot appear in identifiers so with the prefix they clash neither with the C library,
+/// like a function called `puts` or `malloc`, nor with the runtime. `main` is the entry point and
+/// keeps its name.
+fn symbol_name(name: &str) -> String {
+    if name == "main" {
+        name.to_string()
+    } else {
+        format!("degu.{}", name)
+    }
+}
+
+/// Integer literals other than 0 and -1 need no checks when dividing by them.
+fn is_safe_divisor(divisor: &AstNode) -> bool {
+    match &*divisor.expr {
+        Ast::Integer(value) => *value != 0 && *value != -1,
+        _ => false,
+    }
+}
+
 struct Environment {
-    pub vars: HashMap<String, AnyValue>,
+    /// Chain of lexical scopes, innermost last.
+    /// The first one holds the constant values of top-level bindings.
+    scopes: Vec<HashMap<String, AnyValue>>,
     pub defs: HashMap<String, Function>,
 }
 
+impl Environment {
+    fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            defs: HashMap::new(),
+        }
+    }
+
+    fn is_top_level(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        assert!(!self.is_top_level(), "Cannot pop the top-level scope.");
+        self.scopes.pop();
+    }
+
+    /// Binds a name in the innermost scope shadowing any outer bindings with the same name.
+    fn bind(&mut self, name: String, value: AnyValue) {
+        self.scopes.last_mut().unwrap().insert(name, value);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&AnyValue> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Looks up a name bound in the current function, not at the top level.
+    fn lookup_local(&self, name: &str) -> Option<&AnyValue> {
+        self.scopes[1..].iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Hides the local scopes while generating another function since their values belong to
+    /// the current one. Returns them so they can be restored by `leave_function`.
+    fn enter_function(&mut self) -> Vec<HashMap<String, AnyValue>> {
+        self.scopes.split_off(1)
+    }
+
+    fn leave_function(&mut self, locals: Vec<HashMap<String, AnyValue>>) {
+        self.scopes.truncate(1);
+        self.scopes.extend(locals);
+    }
+}
+
 pub struct Compiler {
     // Field order is drop order. Important for LLVM objects.
     // TODO: Figure out a way to not depend on the field order.
     // (Implementing Drop for Compiler is not an options since it prevents field move)
-    function_optimizer: FunctionPassManager,
+    function_optimizer: Option<FunctionPassManager>,
     builder: Builder,
     module: Module,
     context: Context,
 
     pool: CStringInternPool,
     env: Environment,
+    types: TypeTable,
+    /// Closures of the top-level functions used as values.
+    function_values: HashMap<String, AnyValue>,
+    /// Types of the generated instances of polymorphic builtins and functions.
+    instances: HashMap<String, typeck::Type>,
+    /// Parameters and bodies of the polymorphic top-level functions, built for each instance.
+    generic_functions: HashMap<String, (Vec<Param>, AstNode)>,
+    /// Types the variables of the polymorphic function being built stand for.
+    substitution: HashMap<TypeVar, typeck::Type>,
+    /// Instances of the polymorphic functions being built. Recursive calls refer to them.
+    recursive_instances: HashMap<String, String>,
+    /// Structs of the declared types.
+    named_types: HashMap<String, Type>,
+    /// Structs of the union constructors.
+    case_types: HashMap<String, Type>,
+    opt_level: OptLevel,
 }
 
 pub struct Runtime {
@@ -48,22 +155,48 @@ pub struct Runtime {
 #[derive(Debug)]
 pub struct CompilerError {
     message: String,
+    /// Part of the program the error is about, errors of the tools have none.
+    span: Option<Span>,
+}
+
+impl fmt::Display for CompilerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "{} at {}", self.message, pretty_span(span)),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl From<CompilerError> for Diagnostic {
+    fn from(err: CompilerError) -> Self {
+        match err.span {
+            Some(span) => Diagnostic::error(err.message)
+                .with_code(codes::CODEGEN_ERROR)
+                .with_label(span, ""),
+            None => Diagnostic::error(err.message),
+        }
+    }
 }
 
 type CodegenResult = Result<AnyValue, CompilerError>;
 
 impl Compiler {
-    pub fn new() -> Self {
+    pub fn new(opt_level: OptLevel) -> Self {
         let mut pool = CStringInternPool::new();
         let mut context = Context::new();
         let mut module = context.create_module(pool.intern("main"));
         let builder = context.create_builder();
-        let function_optimizer = module.function_pass_manager_builder()
-            .add_instruction_combination_pass()
-            .add_reassociate_pass()
-            .add_gvn_pass()
-            .add_cfg_simplification_pass()
-            .build();
+        let function_optimizer = if opt_level > OptLevel::O0 {
+            Some(module.function_pass_manager_builder()
+                .add_instruction_combination_pass()
+                .add_reassociate_pass()
+                .add_gvn_pass()
+                .add_cfg_simplification_pass()
+                .build())
+        } else {
+            None
+        };
 
         Self {
             pool,
@@ -71,10 +204,16 @@ impl Compiler {
             module,
             builder,
             function_optimizer,
-            env: Environment {
-                vars: HashMap::new(),
-                defs: HashMap::new(),
-            }
+            env: Environment::new(),
+            types: TypeTable::default(),
+            function_values: HashMap::new(),
+            instances: HashMap::new(),
+            generic_functions: HashMap::new(),
+            substitution: HashMap::new(),
+            recursive_instances: HashMap::new(),
+            named_types: HashMap::new(),
+            case_types: HashMap::new(),
+            opt_level,
         }
     }
 
@@ -82,19 +221,46 @@ impl Compiler {
         &self.module
     }
 
-    pub fn compile(&mut self, ast: &AstNode) -> Result<&mut Compiler, CompilerError> {
+    /// Generates the module for a program already checked by `typeck`.
+    pub fn compile(&mut self, ast: &AstNode, types: TypeTable) -> Result<&mut Compiler, CompilerError> {
+        self.types = types;
+        self.declare_types();
         self.init_std();
-        self.codegen(ast)?;
+        self.build_constructors()?;
+        self.build_top_level(ast)?;
 
         let (is_bad, message) = verify_module(
             &self.module,
             VerifierFailureAction::PrintMessageAction);
 
         if is_bad {
-            Err(CompilerError { message })
-        } else {
-            Ok(self)
+            return Err(CompilerError { message, span: None });
+        }
+
+        if self.opt_level > OptLevel::O0 {
+            ModulePassManager::new(self.opt_level).run(&mut self.module);
         }
+
+        Ok(self)
+    }
+
+    pub fn emit_bitcode(&mut self, path: &str) -> Result<(), CompilerError> {
+        self.module.write_bitcode_to_file(self.pool.intern(path))
+            .map_err(|message| CompilerError { message, span: None })
+    }
+
+    /// Emits native assembly or an object file for the host machine.
+    pub fn emit_native(&mut self, path: &str, file_type: CodeGenFileType) -> Result<(), CompilerError> {
+        initialize_native_target();
+
+        let mut target_machine = TargetMachine::host(self.opt_level)
+            .map_err(|message| CompilerError { message, span: None })?;
+
+        self.module.set_target_triple(target_machine.triple());
+        self.module.set_data_layout(target_machine.create_data_layout());
+
+        target_machine.emit_to_file(&self.module, self.pool.intern(path), file_type)
+            .map_err(|message| CompilerError { message, span: None })
     }
 
     pub fn into_runtime(self) -> Runtime {
@@ -117,83 +283,372 @@ impl Compiler {
     //     let Compiler { pool, context, module, builder, .. } = self;
     // }
 
+    fn build_top_level(&mut self, ast: &AstNode) -> Result<(), CompilerError> {
+        let items = match &*ast.expr {
+            Ast::Block(items) => items,
+            _ => return self.codegen(ast).map(|_| ()),
+        };
+
+        // Declare all functions upfront so they can reference themselves or each other
+        // regardless of the definition order.
+        // Polymorphic ones are only built for the instances the program uses.
+        for item in items {
+            if let Ast::Function { prototype: Prototype { name, args, .. }, body } = &*item.expr {
+                if self.types.scheme(name).is_some() {
+                    self.generic_functions.insert(name.clone(), (args.clone(), body.clone()));
+                } else {
+                    self.declare_function(&item.span, name, args)?;
+                }
+            }
+        }
+
+        for item in items {
+            match &*item.expr {
+                Ast::Function { prototype: Prototype { name, .. }, .. }
+                    if self.generic_functions.contains_key(name) => {},
+                Ast::Function { prototype: Prototype { name, args, .. }, body } => {
+                    self.build_function(name, args, body)?;
+                },
+                Ast::Variable { pattern, expr, .. } => {
+                    self.build_global(&item.span, pattern, expr)?;
+                },
+                _ => {
+                    self.codegen(item)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn llvm_type(&self, ty: &typeck::Type) -> Type {
+        match ty {
+            typeck::Type::Unit => self.context.struct_type(&[], false),
+            typeck::Type::Bool => self.context.i1_type(),
+            typeck::Type::Int => self.context.i64_type(),
+            typeck::Type::Float => self.context.f64_type(),
+            typeck::Type::Char => self.context.i32_type(),
+            // NUL terminated UTF-8.
+            typeck::Type::String => Context::pointer_type(self.context.i8_type()),
+            // Tuples are passed around by value.
+            typeck::Type::Tuple(items) => {
+                let fields = items.iter().map(|item| self.llvm_type(item)).collect::<Vec<_>>();
+                self.context.struct_type(&fields, false)
+            },
+            typeck::Type::List(_) => self.list_type(),
+            typeck::Type::Named(name) => Context::pointer_type(self.named_types[name]),
+            typeck::Type::Fn(..) => self.closure_type(),
+            typeck::Type::Var(_) => {
+                unreachable!("Type {} has no value representation.", ty)
+            },
+        }
+    }
+
+    fn unit_value(&mut self) -> AnyValue {
+        let unit_type = self.llvm_type(&typeck::Type::Unit);
+        self.builder.build_const_null(unit_type)
+    }
+
     fn codegen(&mut self, ast: &AstNode) -> CodegenResult {
-        match &*ast.expr {
-            Ast::Number(num) => Ok(self.builder.build_const_fp(self.context.f64_type(), *num)),
+        let ty = self.node_type(ast);
+
+        let value = match &*ast.expr {
+            Ast::Integer(value) => {
+                Ok(self.builder.build_const_int(self.context.i64_type(), *value as u64, true))
+            },
+            Ast::Float(value) => Ok(self.builder.build_const_fp(self.context.f64_type(), *value)),
+            Ast::Bool(value) => {
+                Ok(self.builder.build_const_int(self.context.i1_type(), *value as u64, false))
+            },
+            Ast::Char(value) => {
+                Ok(self.builder.build_const_int(self.context.i32_type(), *value as u64, false))
+            },
+            Ast::Str(value) => {
+                Ok(self.module.add_global_string(value.as_bytes(), Some(self.pool.intern("str"))))
+            },
             Ast::Block(exprs) => self.build_block(&ast.span, exprs),
-            Ast::Ref(name) => self.build_ref(&ast.span, name),
-            Ast::Call { name, args } => self.build_call(&ast.span, name, args),
-            Ast::If { condition, then, el } => self.build_if(condition, then, el),
+            Ast::Ref(name) => self.build_ref(ast, name),
+            Ast::Tuple(items) => self.build_tuple(&ty, items),
+            Ast::List(items) => self.build_list(&ty, items),
+            Ast::Lambda { params, body } => self.build_lambda(params, body, &ty),
+            Ast::Call { name, args } => self.build_call(ast, &ty, name, args),
+            Ast::Apply { callee, args } => self.build_apply(callee, args),
+            Ast::If { condition, then, el } => self.build_if(&ty, condition, then, el),
+            Ast::Match { expr, arms } => self.build_match(&ast.span, &ty, expr, arms),
+            Ast::For { var, start, end, body } => self.build_for(var, start, end, body),
+            Ast::Error => unreachable!("Programs with syntax errors cannot be compiled."),
             Ast::BinOp { kind, lhs, rhs } => self.build_binop(*kind, lhs, rhs),
-            Ast::Function { prototype: Prototype { name, args }, body } => {
-                self.build_function(name, args, body)
+            Ast::Unary { kind, expr } => self.build_unary(*kind, expr),
+            Ast::Variable { pattern, expr, .. } => self.build_variable(pattern, expr),
+            // Types only exist in the type checker and the named structs.
+            Ast::Type { .. } => Ok(self.unit_value()),
+            Ast::Record { fields } => self.build_record(&ty, fields),
+            Ast::Field { expr, field } => self.build_field(expr, field),
+            Ast::Update { record, fields } => self.build_update(&ty, record, fields),
+            Ast::Function { prototype: Prototype { name, args, .. }, body } => {
+                self.build_local_function(name, args, body, &ty)
             },
-            _ => unimplemented!(),
+        }?;
+
+        // Unit has a single value so whatever produced it (like a void call) can be replaced.
+        if ty == typeck::Type::Unit {
+            Ok(self.unit_value())
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Top-level bindings have no function to live in so they are constant folded.
+    fn build_global(&mut self, span: &Span, pattern: &Pattern, expr: &AstNode) -> Result<(), CompilerError> {
+        if !self.is_constant(expr) {
+            return Err(CompilerError {
+                message: "Top-level bindings must be constant expressions".to_string(),
+                span: Some(span.clone()),
+            });
         }
+
+        // The builder folds operations on constants so no instructions are emitted.
+        let value = self.codegen(expr)?;
+        self.bind_pattern(pattern, value);
+
+        Ok(())
+    }
+
+    fn is_constant(&self, ast: &AstNode) -> bool {
+        match &*ast.expr {
+            Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Char(_) | Ast::Str(_) => true,
+            // There are no locals to capture at the top level so the closure is a constant.
+            Ast::Lambda { .. } => true,
+            Ast::Ref(name) => {
+                // Top-level functions used as values are constant closures.
+                self.env.is_top_level()
+                    && (self.env.lookup(name).is_some()
+                        || self.env.defs.contains_key(name)
+                        || self.types.instance(ast).is_some())
+            },
+            // Short-circuiting operators need basic blocks.
+            Ast::BinOp { kind: BinOpKind::And, .. } | Ast::BinOp { kind: BinOpKind::Or, .. } => false,
+            // Appending calls a function.
+            Ast::BinOp { kind: BinOpKind::Append, .. } => false,
+            // Checking the divisor needs basic blocks.
+            Ast::BinOp { kind, lhs, rhs }
+                if (*kind == BinOpKind::Div || *kind == BinOpKind::Mod)
+                    && self.node_type(lhs) == typeck::Type::Int
+                    && !is_safe_divisor(rhs) => false,
+            Ast::BinOp { lhs, rhs, .. } => self.is_constant(lhs) && self.is_constant(rhs),
+            Ast::Unary { expr, .. } => self.is_constant(expr),
+            Ast::Tuple(items) | Ast::List(items) => items.iter().all(|item| self.is_constant(item)),
+            Ast::Record { fields } => fields.iter().all(|(_, value)| self.is_constant(value)),
+            _ => false,
+        }
+    }
+
+    fn build_variable(&mut self, pattern: &Pattern, expr: &AstNode) -> CodegenResult {
+        let value = self.codegen(expr)?;
+        self.bind_pattern(pattern, value);
+
+        Ok(self.unit_value())
+    }
+
+    /// Binds the names of a `let` pattern the type checker matched against the value.
+    fn bind_pattern(&mut self, pattern: &Pattern, value: AnyValue) {
+        match pattern {
+            Pattern::Wildcard => {},
+            Pattern::Ident(name) => self.env.bind(name.clone(), value),
+            Pattern::Tuple(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let element = self.builder.build_extract_value(&value, index as u32, None);
+                    self.bind_pattern(item, element);
+                }
+            },
+            Pattern::Literal(_) | Pattern::List(_) | Pattern::Cons(..) | Pattern::Constructor(..) => {
+                unreachable!("Patterns of `let` must match any value.")
+            },
+        }
+    }
+
+    fn build_tuple(&mut self, ty: &typeck::Type, items: &Vec<AstNode>) -> CodegenResult {
+        let values = items
+            .iter()
+            .map(|item| self.codegen(item))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Unit is replaced by its constant anyway.
+        if values.is_empty() {
+            return Ok(self.unit_value());
+        }
+
+        let tuple_type = self.llvm_type(ty);
+        let mut tuple = self.builder.build_undef(tuple_type);
+
+        for (index, value) in values.iter().enumerate() {
+            tuple = self.builder.build_insert_value(&tuple, value, index as u32, None);
+        }
+
+        Ok(tuple)
+    }
+
+    /// Generates an expression in its own scope so its bindings do not leak out.
+    fn build_scoped(&mut self, ast: &AstNode) -> CodegenResult {
+        self.env.push_scope();
+        let value = self.codegen(ast);
+        self.env.pop_scope();
+        value
     }
 
     fn build_block(&mut self, span: &Span, exprs: &Vec<AstNode>) -> CodegenResult {
-        exprs
+        // Bindings introduced in the block are only visible until its end.
+        self.env.push_scope();
+
+        let values = exprs
             .iter()
             .map(|expr| self.codegen(expr))
-            .collect::<Result<Vec<_>, _>>()?
+            .collect::<Result<Vec<_>, _>>();
+
+        self.env.pop_scope();
+
+        values?
             .into_iter()
             .last()
             .ok_or(CompilerError {
-                message: format!(
-                    "Found empty block which is invalid value! {:?}",
-                    pretty_span(span)),
+                message: "Found empty block which is invalid value!".to_string(),
+                span: Some(span.clone()),
             })
     }
 
-    fn build_ref(&mut self, span: &Span, name: &String) -> CodegenResult {
+    fn build_ref(&mut self, ast: &AstNode, name: &String) -> CodegenResult {
+        let span = &ast.span;
+
         if name == "_" {
             return Err(CompilerError {
-                message: format!(
-                    "Illegal reference _ at {:?}",
-                    pretty_span(span)),
+                message: "Illegal reference _".to_string(),
+                span: Some(span.clone()),
             });
         }
 
-        self.env.vars.get(name)
+        if self.env.lookup(name).is_none() {
+            if let Some(function) = self.function_name(ast, name)? {
+                return self.build_function_value(span, &function);
+            }
+        }
+
+        self.env.lookup(name)
             .map(|var| var.clone())
-            .ok_or(CompilerError{
-                message: format!(
-                    "Unknown variable ref {:?} at {:?}",
-                    name,
-                    pretty_span(span)),
+            .ok_or(CompilerError {
+                message: format!("Variable {:?} is used before being defined", name),
+                span: Some(span.clone()),
             })
     }
 
-    fn build_call(&mut self, span: &Span, name: &String, args: &Vec<AstNode>) -> CodegenResult {
+    /// Name of the function a reference to a top-level function or builtin calls.
+    /// Polymorphic ones are resolved to the instance the type checker found for the node.
+    fn function_name(&mut self, ast: &AstNode, name: &String) -> Result<Option<String>, CompilerError> {
+        if let Some(types) = self.instance_types(ast) {
+            return if self.generic_functions.contains_key(name) {
+                self.build_function_instance(name, &types).map(Some)
+            } else {
+                self.build_generic_instance(name, &types).map(Some)
+            };
+        }
+
+        // Inside of its body a polymorphic function refers to itself without an instance.
+        if let Some(instance) = self.recursive_instances.get(name) {
+            return Ok(Some(instance.clone()));
+        }
+
+        if self.env.defs.contains_key(name) {
+            Ok(Some(name.clone()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Type of a top-level function or an instance of a polymorphic one.
+    fn function_type(&self, name: &str) -> typeck::Type {
+        self.types.def(name)
+            .or_else(|| self.instances.get(name))
+            .cloned()
+            .expect("Functions must be typed before being used.")
+    }
+
+    fn build_call(
+        &mut self,
+        ast: &AstNode,
+        ty: &typeck::Type,
+        name: &String,
+        args: &Vec<AstNode>) -> CodegenResult
+    {
+        let span = &ast.span;
+
         let values = args
             .iter()
             .map(|arg| self.codegen(arg))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let f = self.env.defs.get(name)
-            .ok_or(CompilerError{
-                message: format!(
-                    "Unknown function ref {:?} at {:?}",
-                    name,
-                    pretty_span(span)),
+        // Variables shadow functions with the same name.
+        if let Some(closure) = self.env.lookup(name).cloned() {
+            let arg_types = args.iter().map(|arg| self.node_type(arg)).collect();
+            let closure_type = typeck::Type::function(arg_types, ty.clone());
+            return Ok(self.build_closure_call(closure, &closure_type, &values));
+        }
+
+        let function = self.function_name(ast, name)?
+            .ok_or(CompilerError {
+                message: format!("Unknown function ref {:?}", name),
+                span: Some(span.clone()),
             })?;
+        let f = self.env.defs[&function].clone();
+
+        if name == "main" {
+            return Err(CompilerError {
+                message: "The main function cannot be called".to_string(),
+                span: Some(span.clone()),
+            });
+        }
+
+        let arity = f.params().len();
 
-        self.builder.build_call(f, &values, None)
-            .map_err(|err| CompilerError { message: format!("{:?}", err) })
+        // Partial application, the closure of the function collects the rest of the arguments.
+        if values.len() < arity {
+            let closure = self.build_function_value(span, &function)?;
+            let closure_type = self.function_type(&function);
+            return Ok(self.build_closure_call(closure, &closure_type, &values));
+        }
+
+        let (direct, rest) = values.split_at(arity);
+        let value = self.builder.build_call(&f, direct, None)
+            .map_err(|err| CompilerError { message: format!("{:?}", err), span: None })?;
+
+        if rest.is_empty() {
+            return Ok(value);
+        }
+
+        // The function returns a closure which takes the rest of the arguments.
+        let rest_types = args[arity..].iter().map(|arg| self.node_type(arg)).collect();
+        let closure_type = typeck::Type::function(rest_types, ty.clone());
+        Ok(self.build_closure_call(value, &closure_type, rest))
     }
 
-    fn build_if(&mut self, cond: &AstNode, then: &AstNode, el: &Option<AstNode>) -> CodegenResult {
-        let cond = {
-            let cond = self.codegen(cond)?;
-            let zero = self.builder.build_const_fp(self.context.f64_type(), 0.0);
+    fn build_apply(&mut self, callee: &AstNode, args: &Vec<AstNode>) -> CodegenResult {
+        let closure = self.codegen(callee)?;
 
-            self.builder.build_fp_cmp(
-                RealPredicate::ONE,
-                &cond,
-                &zero,
-                Some(self.pool.intern("ifcond")))
-        };
+        let values = args
+            .iter()
+            .map(|arg| self.codegen(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let closure_type = self.node_type(callee);
+        Ok(self.build_closure_call(closure, &closure_type, &values))
+    }
+
+    fn build_if(
+        &mut self,
+        ty: &typeck::Type,
+        cond: &AstNode,
+        then: &AstNode,
+        el: &Option<AstNode>) -> CodegenResult
+    {
+        let cond = self.codegen(cond)?;
 
         let mut f = self.builder.get_insert_block().parent();
 
@@ -204,7 +659,7 @@ impl Compiler {
         self.builder.build_conditional_branch(&cond, &then_block, &else_block);
 
         self.builder.position_at_end(&then_block);
-        let then = self.codegen(then)?;
+        let then = self.build_scoped(then)?;
         self.builder.build_branch(&merge_block);
 
         // Codegen of `then` can change the current block so update `then_block` for the PHI.
@@ -212,12 +667,10 @@ impl Compiler {
 
         self.builder.position_at_end(&else_block);
         let el = if let Some(ref el) = el {
-            self.codegen(el)?
+            self.build_scoped(el)?
         } else {
-            // FIXME: This is incorrect and should not be reached with a proper type system!
-            // The only allowed follow ups should be the same type as the `then` block
-            // or a `never` value - return/exit/unimplemented/unreachable.
-            self.builder.build_const_fp(self.context.f64_type(), std::f64::INFINITY)
+            // The type checker only allows a missing `else` when `then` is of type unit.
+            self.unit_value()
         };
         self.builder.build_branch(&merge_block);
 
@@ -226,7 +679,7 @@ impl Compiler {
 
         self.builder.position_at_end(&merge_block);
         let mut phi = self.builder.build_phi(
-            self.context.f64_type(),
+            self.llvm_type(ty),
             Some(self.pool.intern("iftmp"))
         );
 
@@ -238,12 +691,189 @@ impl Compiler {
         Ok(phi.to_value())
     }
 
+    fn build_for(
+        &mut self,
+        var: &String,
+        start: &AstNode,
+        end: &AstNode,
+        body: &AstNode) -> CodegenResult
+    {
+        let i64_type = self.context.i64_type();
+
+        // Both bounds are evaluated once before entering the loop.
+        let start = self.codegen(start)?;
+        let end = self.codegen(end)?;
+
+        let preheader_block = self.builder.get_insert_block();
+        let mut f = preheader_block.parent();
+
+        let header_block = BasicBlock::new(self.pool.intern("forheader"), &mut f);
+        let body_block = BasicBlock::new(self.pool.intern("forbody"), &mut f);
+        let exit_block = BasicBlock::new(self.pool.intern("forexit"), &mut f);
+
+        self.builder.build_branch(&header_block);
+
+        self.builder.position_at_end(&header_block);
+        let mut phi = self.builder.build_phi(i64_type, Some(self.pool.intern(var.as_ref())));
+        phi.add_incoming(&vec![(start, preheader_block)]);
+
+        let cond = self.builder.build_int_cmp(
+            IntPredicate::SLE,
+            phi.as_value(),
+            &end,
+            Some(self.pool.intern("forcond")));
+        self.builder.build_conditional_branch(&cond, &body_block, &exit_block);
+
+        self.builder.position_at_end(&body_block);
+        self.env.push_scope();
+        if var != "_" {
+            self.env.bind(var.clone(), phi.as_value().clone());
+        }
+        let body = self.codegen(body);
+        self.env.pop_scope();
+        body?;
+
+        let one = self.builder.build_const_int(i64_type, 1, true);
+        let next = self.builder.build_add(phi.as_value(), &one, Some(self.pool.intern("fornext")));
+        self.builder.build_branch(&header_block);
+
+        // Codegen of `body` can change the current block so get the latch for the PHI.
+        let latch_block = self.builder.get_insert_block();
+        phi.add_incoming(&vec![(next, latch_block)]);
+
+        self.builder.position_at_end(&exit_block);
+
+        // Loops are evaluated only for their side effects.
+        Ok(self.unit_value())
+    }
+
     fn build_binop(
         &mut self,
         kind: BinOpKind,
         lhs: &AstNode,
         rhs: &AstNode) -> CodegenResult
     {
+        if kind == BinOpKind::And || kind == BinOpKind::Or {
+            return self.build_logical(kind, lhs, rhs);
+        }
+
+        if kind == BinOpKind::Cons || kind == BinOpKind::Append {
+            return self.build_list_binop(kind, lhs, rhs);
+        }
+
+        // Both operands are of the same type which decides what instructions are used.
+        let operand = self.node_type(lhs);
+        let checked = (kind == BinOpKind::Div || kind == BinOpKind::Mod)
+            && operand == typeck::Type::Int
+            && !is_safe_divisor(rhs);
+
+        let lhs = self.codegen(&lhs)?;
+        let rhs = self.codegen(&rhs)?;
+
+        if checked {
+            return Ok(self.build_checked_division(kind, &lhs, &rhs));
+        }
+
+        Ok(match operand {
+            typeck::Type::Float => self.build_fp_binop(kind, &lhs, &rhs),
+            typeck::Type::Int | typeck::Type::Bool | typeck::Type::Char => {
+                self.build_int_binop(kind, &lhs, &rhs)
+            },
+            typeck::Type::String => self.build_string_binop(kind, &lhs, &rhs),
+            // Unit has a single value so it is always equal to itself.
+            typeck::Type::Unit => {
+                let equal = (kind == BinOpKind::Eq) as u64;
+                self.builder.build_const_int(self.context.i1_type(), equal, false)
+            },
+            _ => unreachable!("Type {} has no operators.", operand),
+        })
+    }
+
+    fn build_int_binop(&mut self, kind: BinOpKind, lhs: &AnyValue, rhs: &AnyValue) -> AnyValue {
+        use IntPredicate as IP;
+        use BinOpKind::*;
+
+        fn build_int_cmp(
+            compiler: &mut Compiler,
+            p: IntPredicate,
+            l: &AnyValue,
+            r: &AnyValue) -> AnyValue
+        {
+            compiler.builder.build_int_cmp(p, l, r, Some(compiler.pool.intern("cmptmp")))
+        }
+
+        match kind {
+            Eq           => build_int_cmp(self, IP::EQ, lhs, rhs),
+            NotEq        => build_int_cmp(self, IP::NE, lhs, rhs),
+            GreaterThan  => build_int_cmp(self, IP::SGT, lhs, rhs),
+            GreaterEq    => build_int_cmp(self, IP::SGE, lhs, rhs),
+            LessThan     => build_int_cmp(self, IP::SLT, lhs, rhs),
+            LessEq       => build_int_cmp(self, IP::SLE, lhs, rhs),
+            Add          => self.builder.build_add(lhs, rhs, Some(self.pool.intern("addtmp"))),
+            Sub          => self.builder.build_sub(lhs, rhs, Some(self.pool.intern("subtmp"))),
+            Mul          => self.builder.build_mul(lhs, rhs, Some(self.pool.intern("multmp"))),
+            Div          => self.builder.build_sdiv(lhs, rhs, Some(self.pool.intern("divtmp"))),
+            Mod          => self.builder.build_srem(lhs, rhs, Some(self.pool.intern("modtmp"))),
+            And | Or | Cons | Append => unreachable!(),
+        }
+    }
+
+    /// Integer division exits the program when the divisor is zero or the quotient overflows like
+    /// in `INT_MIN / -1`. Both are undefined behaviour in LLVM.
+    fn build_checked_division(&mut self, kind: BinOpKind, lhs: &AnyValue, rhs: &AnyValue) -> AnyValue {
+        let i64_type = self.context.i64_type();
+        let mut f = self.builder.get_insert_block().parent();
+
+        let zero_block = BasicBlock::new(self.pool.intern("divzero"), &mut f);
+        let minus_one_block = BasicBlock::new(self.pool.intern("divminusone"), &mut f);
+        let overflow_block = BasicBlock::new(self.pool.intern("divoverflow"), &mut f);
+        let divide_block = BasicBlock::new(self.pool.intern("divide"), &mut f);
+
+        let (zero_message, overflow_message) = if kind == BinOpKind::Div {
+            ("division by zero", "division overflow")
+        } else {
+            ("remainder by zero", "remainder overflow")
+        };
+
+        let zero = self.builder.build_const_int(i64_type, 0, true);
+        let is_zero = self.builder.build_int_cmp(
+            IntPredicate::EQ,
+            rhs,
+            &zero,
+            Some(self.pool.intern("iszero")));
+        self.builder.build_conditional_branch(&is_zero, &zero_block, &minus_one_block);
+
+        self.builder.position_at_end(&zero_block);
+        self.build_panic(zero_message);
+
+        // Only the smallest integer divided by -1 does not fit.
+        self.builder.position_at_end(&minus_one_block);
+        let minus_one = self.builder.build_const_int(i64_type, -1i64 as u64, true);
+        let is_minus_one = self.builder.build_int_cmp(
+            IntPredicate::EQ,
+            rhs,
+            &minus_one,
+            Some(self.pool.intern("isminusone")));
+        self.builder.build_conditional_branch(&is_minus_one, &overflow_block, &divide_block);
+
+        self.builder.position_at_end(&overflow_block);
+        let min = self.builder.build_const_int(i64_type, i64::MIN as u64, true);
+        let is_min = self.builder.build_int_cmp(
+            IntPredicate::EQ,
+            lhs,
+            &min,
+            Some(self.pool.intern("ismin")));
+        let panic_block = BasicBlock::new(self.pool.intern("divpanic"), &mut f);
+        self.builder.build_conditional_branch(&is_min, &panic_block, &divide_block);
+
+        self.builder.position_at_end(&panic_block);
+        self.build_panic(overflow_message);
+
+        self.builder.position_at_end(&divide_block);
+        self.build_int_binop(kind, lhs, rhs)
+    }
+
+    fn build_fp_binop(&mut self, kind: BinOpKind, lhs: &AnyValue, rhs: &AnyValue) -> AnyValue {
         use RealPredicate as RP;
         use BinOpKind::*;
 
@@ -253,90 +883,209 @@ impl Compiler {
             l: &AnyValue,
             r: &AnyValue) -> AnyValue
         {
-            let cmp = compiler.builder.build_fp_cmp(p, l, r, Some(compiler.pool.intern("cmptmp")));
+            compiler.builder.build_fp_cmp(p, l, r, Some(compiler.pool.intern("cmptmp")))
+        }
 
-            compiler.builder.build_cast_uint_to_fp(
-                cmp,
-                compiler.context.f64_type(),
-                Some(compiler.pool.intern("booltmp")),
-            )
+        match kind {
+            Eq           => build_fp_cmp(self, RP::UEQ, lhs, rhs),
+            NotEq        => build_fp_cmp(self, RP::UNE, lhs, rhs),
+            GreaterThan  => build_fp_cmp(self, RP::UGT, lhs, rhs),
+            GreaterEq    => build_fp_cmp(self, RP::UGE, lhs, rhs),
+            LessThan     => build_fp_cmp(self, RP::ULT, lhs, rhs),
+            LessEq       => build_fp_cmp(self, RP::ULE, lhs, rhs),
+            Add          => self.builder.build_fp_add(lhs, rhs, Some(self.pool.intern("addtmp"))),
+            Sub          => self.builder.build_fp_sub(lhs, rhs, Some(self.pool.intern("subtmp"))),
+            Mul          => self.builder.build_fp_mul(lhs, rhs, Some(self.pool.intern("multmp"))),
+            Div          => self.builder.build_fp_div(lhs, rhs, Some(self.pool.intern("divtmp"))),
+            Mod          => self.builder.build_fp_rem(lhs, rhs, Some(self.pool.intern("modtmp"))),
+            And | Or | Cons | Append => unreachable!(),
         }
+    }
 
-        let lhs = self.codegen(&lhs)?;
-        let rhs = self.codegen(&rhs)?;
+    /// Strings are concatenated and compared by the runtime.
+    fn build_string_binop(&mut self, kind: BinOpKind, lhs: &AnyValue, rhs: &AnyValue) -> AnyValue {
+        let args = [lhs.clone(), rhs.clone()];
+
+        if kind == BinOpKind::Add {
+            return self.build_builtin_call("string_concat", &args);
+        }
+
+        // The order is negative, zero or positive like the result of `strcmp`.
+        let order = self.build_builtin_call("string_compare", &args);
+        let zero = self.builder.build_const_int(self.context.i64_type(), 0, true);
+        self.build_int_binop(kind, &order, &zero)
+    }
+
+    /// Short-circuiting `and` and `or`. The right side is only evaluated when the left one
+    /// does not already determine the result.
+    fn build_logical(&mut self, kind: BinOpKind, lhs: &AstNode, rhs: &AstNode) -> CodegenResult {
+        let lhs = self.codegen(lhs)?;
+
+        // Codegen of `lhs` can change the current block so get it after for the PHI.
+        let lhs_block = self.builder.get_insert_block();
+        let mut f = lhs_block.parent();
+
+        let rhs_block = BasicBlock::new(self.pool.intern("logicrhs"), &mut f);
+        let merge_block = BasicBlock::new(self.pool.intern("logicmerge"), &mut f);
+
+        let short_circuit = if kind == BinOpKind::And {
+            self.builder.build_conditional_branch(&lhs, &rhs_block, &merge_block);
+            0
+        } else {
+            self.builder.build_conditional_branch(&lhs, &merge_block, &rhs_block);
+            1
+        };
+
+        self.builder.position_at_end(&rhs_block);
+        let rhs = self.codegen(rhs)?;
+        self.builder.build_branch(&merge_block);
+
+        // Codegen of `rhs` can change the current block so update `rhs_block` for the PHI.
+        let rhs_block = self.builder.get_insert_block();
+
+        self.builder.position_at_end(&merge_block);
+        let short_circuit = self.builder.build_const_int(self.context.i1_type(), short_circuit, false);
+        let mut phi = self.builder.build_phi(
+            self.context.i1_type(),
+            Some(self.pool.intern("logictmp"))
+        );
+
+        phi.add_incoming(&vec![
+            (short_circuit, lhs_block),
+            (rhs, rhs_block),
+        ]);
+
+        Ok(phi.to_value())
+    }
+
+    fn build_unary(&mut self, kind: UnOpKind, expr: &AstNode) -> CodegenResult {
+        let is_float = self.node_type(expr) == typeck::Type::Float;
+        let value = self.codegen(expr)?;
 
         Ok(match kind {
-            Eq           => build_fp_cmp(self, RP::UEQ, &lhs, &rhs),
-            NotEq        => build_fp_cmp(self, RP::UNE, &lhs, &rhs),
-            GreaterThan  => build_fp_cmp(self, RP::UGT, &lhs, &rhs),
-            GreaterEq    => build_fp_cmp(self, RP::UGE, &lhs, &rhs),
-            LessThan     => build_fp_cmp(self, RP::ULT, &lhs, &rhs),
-            LessEq       => build_fp_cmp(self, RP::ULE, &lhs, &rhs),
-            Add          => self.builder.build_fp_add(&lhs, &rhs, Some(self.pool.intern("addtmp"))),
-            Sub          => self.builder.build_fp_sub(&lhs, &rhs, Some(self.pool.intern("subtmp"))),
-            Mul          => self.builder.build_fp_mul(&lhs, &rhs, Some(self.pool.intern("multmp"))),
+            UnOpKind::Neg if is_float => {
+                self.builder.build_fp_neg(&value, Some(self.pool.intern("negtmp")))
+            },
+            UnOpKind::Neg => self.builder.build_neg(&value, Some(self.pool.intern("negtmp"))),
+            UnOpKind::Not => self.builder.build_not(&value, Some(self.pool.intern("nottmp"))),
         })
     }
 
-    fn build_function(
+    fn declare_function(
         &mut self,
+        span: &Span,
         name: &String,
-        args: &Vec<String>,
-        body: &AstNode) -> CodegenResult
+        args: &Vec<Param>) -> Result<(), CompilerError>
     {
-        let f64_type = self.context.f64_type();
-        let void_type = self.context.void_type();
+        if self.env.defs.contains_key(name) {
+            return Err(CompilerError {
+                message: format!("Function {:?} is already defined", name),
+                span: Some(span.clone()),
+            });
+        }
+
+        // Annotations are part of the checked type so they pick the LLVM types as well.
+        let (arg_types, ret_type) = self.types.def(name)
+            .and_then(|ty| ty.split_function(args.len()))
+            .expect("Functions must be typed before being declared.");
+
         let is_main = name == "main";
 
-        let mut f = {
-            let ret_type = if is_main { void_type } else { f64_type };
-            let arg_types = if is_main { vec![] } else { vec![f64_type; args.len()] };
+        let f = {
+            let ret_type = if is_main { self.context.void_type() } else { self.llvm_type(&ret_type) };
+            let arg_types = if is_main {
+                vec![]
+            } else {
+                arg_types.iter().map(|ty| self.llvm_type(ty)).collect()
+            };
 
             self.module.function_prototype(
-                Some(self.pool.intern(name.as_ref())),
+                Some(self.pool.intern(&symbol_name(name))),
                 Context::function_type(ret_type, &arg_types, false),
             )
         };
 
-        self.env.vars = f.params()
-            .into_iter()
-            .zip(args)
-            .fold(HashMap::new(), |mut acc, (mut param, name)| {
-                param.set_name(self.pool.intern(name.as_ref()));
-                acc.insert(name.clone(), param);
-                acc
-            });
+        self.env.defs.insert(name.clone(), f);
+
+        Ok(())
+    }
+
+    fn build_function(
+        &mut self,
+        name: &String,
+        args: &Vec<Param>,
+        body: &AstNode) -> CodegenResult
+    {
+        let is_main = name == "main";
+
+        let mut f = self.env.defs.get(name)
+            .cloned()
+            .expect("Functions must be declared before being built.");
+
+        self.env.push_scope();
+
+        for (mut param, arg) in f.params().into_iter().zip(args) {
+            param.set_name(self.pool.intern(arg.name.as_ref()));
+            self.env.bind(arg.name.clone(), param);
+        }
+
+        // The unit parameter of `main` has no LLVM parameter since the runtime passes nothing.
+        if is_main {
+            for arg in args {
+                let unit = self.unit_value();
+                self.env.bind(arg.name.clone(), unit);
+            }
+        }
 
         let bb = BasicBlock::new(self.pool.intern("entry"), &mut f);
         self.builder.position_at_end(&bb);
 
-        let ret = self.codegen(&body)?;
+        let ret = self.codegen(&body);
+        self.env.pop_scope();
+        let ret = ret?;
         if is_main {
             self.builder.build_ret_void();
         } else {
             self.builder.build_ret(&ret);
         }
 
-        if verify_function(&f, VerifierFailureAction::PrintMessageAction) {
+        self.finish_function(&mut f)?;
+
+        Ok(f.to_value())
+    }
+
+    /// Verifies and optimizes a completely built function.
+    fn finish_function(&mut self, f: &mut Function) -> Result<(), CompilerError> {
+        if verify_function(f, VerifierFailureAction::PrintMessageAction) {
             return Err(CompilerError {
                 message: format!("{:?}", f),
+                span: None,
             });
         }
 
-        self.function_optimizer.run(&mut f);
-
-        self.env.defs.insert(name.clone(), f.clone());
+        if let Some(ref function_optimizer) = self.function_optimizer {
+            function_optimizer.run(f);
+        }
 
-        Ok(f.to_value())
+        Ok(())
     }
 }
 
 impl Runtime {
-    pub fn run_main(&mut self) {
+    pub fn run_main(&mut self) -> Result<(), CompilerError> {
+        if !self.env.defs.contains_key("main") {
+            return Err(CompilerError {
+                message: "Cannot run a program without a main function".to_string(),
+                span: None,
+            });
+        }
+
         let main: extern fn() = unsafe {
             std::mem::transmute(self.ee.function_address(self.pool.intern("main")))
         };
 
         main();
+
+        Ok(())
     }
 }
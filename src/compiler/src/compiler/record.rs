@@ -0,0 +1,137 @@
+//! Records.
+//!
+//! A record is a pointer to a named struct with the fields in the order of the declaration.
+//! Records are immutable so updating one copies it. Like list cells they are allocated on the
+//! heap and the ones built at the top level are constant globals.
+
+use llvm_wrap::prelude::*;
+
+use syntax::parser::ast::AstNode;
+
+use crate::typeck::{self, TypeDecl};
+
+use super::{CodegenResult, Compiler};
+
+impl Compiler {
+    /// Creates the named structs of the declared types. All of them exist before any body is set
+    /// so records can contain each other.
+    pub(super) fn declare_types(&mut self) {
+        let mut names = self.types.decls().keys().cloned().collect::<Vec<_>>();
+        names.sort();
+
+        for name in &names {
+            let ty = self.context.named_struct_type(self.pool.intern(name.as_ref()));
+            self.named_types.insert(name.clone(), ty);
+        }
+
+        for name in &names {
+            let fields = match &self.types.decls()[name] {
+                TypeDecl::Record(fields) => fields
+                    .iter()
+                    .map(|(_, ty)| self.llvm_type(ty))
+                    .collect::<Vec<_>>(),
+                // Only the tag is known without the constructor.
+                TypeDecl::Union(_) => vec![self.context.i32_type()],
+            };
+
+            self.named_types[name].set_struct_body(&fields, false);
+        }
+    }
+
+    fn field_index(&self, record: &str, field: &str) -> u32 {
+        self.types.record_fields(record)
+            .iter()
+            .position(|(name, _)| name == field)
+            .expect("Fields must be checked before they are used.") as u32
+    }
+
+    pub(super) fn build_record(
+        &mut self,
+        ty: &typeck::Type,
+        fields: &Vec<(String, AstNode)>) -> CodegenResult
+    {
+        let record = match ty {
+            typeck::Type::Named(name) => name,
+            _ => unreachable!("Records must be of a named type, found {}.", ty),
+        };
+
+        // The values are evaluated in the order they are written.
+        let mut values = vec![None; fields.len()];
+        for (field, value) in fields {
+            let index = self.field_index(record, field);
+            values[index as usize] = Some(self.codegen(value)?);
+        }
+
+        let values = values
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .expect("Records must set all of their fields.");
+
+        Ok(self.build_record_value(record, &values))
+    }
+
+    pub(super) fn build_field(&mut self, expr: &AstNode, field: &String) -> CodegenResult {
+        let record = match self.node_type(expr) {
+            typeck::Type::Named(name) => name,
+            ty => unreachable!("Only records have fields, found {}.", ty),
+        };
+
+        let value = self.codegen(expr)?;
+        let index = self.field_index(&record, field);
+
+        let ptr = self.builder.build_struct_gep(&value, index, None);
+        Ok(self.builder.build_load(&ptr, Some(self.pool.intern(field.as_ref()))))
+    }
+
+    pub(super) fn build_update(
+        &mut self,
+        ty: &typeck::Type,
+        record: &AstNode,
+        fields: &Vec<(String, AstNode)>) -> CodegenResult
+    {
+        let name = match ty {
+            typeck::Type::Named(name) => name,
+            _ => unreachable!("Records must be of a named type, found {}.", ty),
+        };
+
+        let old = self.codegen(record)?;
+
+        let mut values = vec![None; self.types.record_fields(name).len()];
+        for (field, value) in fields {
+            let index = self.field_index(name, field);
+            values[index as usize] = Some(self.codegen(value)?);
+        }
+
+        // The fields that are not replaced are copied.
+        let values = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| value.unwrap_or_else(|| {
+                let ptr = self.builder.build_struct_gep(&old, index as u32, None);
+                self.builder.build_load(&ptr, None)
+            }))
+            .collect::<Vec<_>>();
+
+        Ok(self.build_record_value(name, &values))
+    }
+
+    /// Allocates a record with the values of its fields in the order of the declaration.
+    fn build_record_value(&mut self, record: &str, values: &[AnyValue]) -> AnyValue {
+        let struct_type = self.named_types[record];
+
+        // Top-level records are constant folded.
+        if self.env.is_top_level() {
+            let value = Context::const_named_struct(struct_type, values);
+            return self.module.add_global_constant(&value, Some(self.pool.intern(record)));
+        }
+
+        let ptr = self.builder.build_malloc(struct_type, Some(self.pool.intern(record)));
+
+        for (index, value) in values.iter().enumerate() {
+            let field = self.builder.build_struct_gep(&ptr, index as u32, None);
+            self.builder.build_store(value, &field);
+        }
+
+        ptr
+    }
+}
@@ -1,33 +1,478 @@
-use llvm_wrap::prelude::Context;
+use llvm_wrap::builder::RealPredicate;
+use llvm_wrap::prelude::{AnyValue, BasicBlock, Context, Function};
 
-use super::{Compiler, Runtime};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::process;
+
+use crate::typeck::{Scheme, Type, TypeVar};
+
+use super::{Compiler, CompilerError, Runtime, instance_name};
+
+/// Function implemented by the runtime.
+/// The JIT maps it to `address` and executables link it from `runtime.c`.
+struct Extern {
+    name: &'static str,
+    args: Vec<Type>,
+    ret: Type,
+    address: usize,
+}
+
+/// Function generated directly as IR.
+struct Intrinsic {
+    name: &'static str,
+    args: Vec<Type>,
+    ret: Type,
+    build: fn(&mut Compiler, &[AnyValue]) -> AnyValue,
+}
+
+/// Polymorphic function generated as IR for every instance a program uses.
+struct Generic {
+    name: &'static str,
+    /// Number of type variables, `Type::Var(0)` and up in `args` and `ret`.
+    vars: TypeVar,
+    args: Vec<Type>,
+    ret: Type,
+    /// Builds the whole body given the parameters and the types of the variables.
+    build: fn(&mut Compiler, &[AnyValue], &[Type]),
+}
+
+impl Generic {
+    fn scheme(&self) -> Scheme {
+        Scheme {
+            vars: (0..self.vars).collect(),
+            classes: HashMap::new(),
+            ty: Type::function(self.args.clone(), self.ret.clone()),
+        }
+    }
+}
+
+fn externs() -> Vec<Extern> {
+    vec![
+        Extern {
+            name: "print_number",
+            args: vec![Type::Float],
+            ret: Type::Unit,
+            address: print_number as usize,
+        },
+        Extern {
+            name: "print_int",
+            args: vec![Type::Int],
+            ret: Type::Unit,
+            address: print_int as usize,
+        },
+        Extern {
+            name: "print_string",
+            args: vec![Type::String],
+            ret: Type::Unit,
+            address: print_string as usize,
+        },
+        Extern {
+            name: "print_char",
+            args: vec![Type::Char],
+            ret: Type::Unit,
+            address: print_char as usize,
+        },
+        Extern {
+            name: "char_of_int",
+            args: vec![Type::Int],
+            ret: Type::Char,
+            address: char_of_int as usize,
+        },
+        Extern {
+            name: "string_of_char",
+            args: vec![Type::Char],
+            ret: Type::String,
+            address: string_of_char as usize,
+        },
+        Extern {
+            name: "char_of_string",
+            args: vec![Type::String],
+            ret: Type::Char,
+            address: char_of_string as usize,
+        },
+        Extern {
+            name: "string_concat",
+            args: vec![Type::String, Type::String],
+            ret: Type::String,
+            address: string_concat as usize,
+        },
+        Extern {
+            name: "string_length",
+            args: vec![Type::String],
+            ret: Type::Int,
+            address: string_length as usize,
+        },
+        Extern {
+            name: "string_compare",
+            args: vec![Type::String, Type::String],
+            ret: Type::Int,
+            address: string_compare as usize,
+        },
+    ]
+}
+
+/// Functions only generated code calls, programs cannot refer to them.
+fn runtime() -> Vec<Extern> {
+    vec![
+        Extern {
+            name: "degu_panic",
+            args: vec![Type::String],
+            ret: Type::Unit,
+            address: degu_panic as usize,
+        },
+    ]
+}
+
+fn intrinsics() -> Vec<Intrinsic> {
+    vec![
+        Intrinsic {
+            name: "float_of_int",
+            args: vec![Type::Int],
+            ret: Type::Float,
+            build: |compiler, args| {
+                let f64_type = compiler.context.f64_type();
+                compiler.builder.build_cast_int_to_fp(&args[0], f64_type, None)
+            },
+        },
+        Intrinsic {
+            name: "int_of_float",
+            args: vec![Type::Float],
+            ret: Type::Int,
+            build: Compiler::build_int_of_float,
+        },
+        Intrinsic {
+            name: "int_of_char",
+            args: vec![Type::Char],
+            ret: Type::Int,
+            build: |compiler, args| {
+                let i64_type = compiler.context.i64_type();
+                compiler.builder.build_zext(&args[0], i64_type, None)
+            },
+        },
+    ]
+}
+
+fn generics() -> Vec<Generic> {
+    let a = Type::Var(0);
+    let b = Type::Var(1);
+    let list = |item: &Type| Type::List(Box::new(item.clone()));
+
+    vec![
+        Generic {
+            name: "map",
+            vars: 2,
+            args: vec![Type::function(vec![a.clone()], b.clone()), list(&a)],
+            ret: list(&b),
+            build: Compiler::build_list_map,
+        },
+        Generic {
+            name: "filter",
+            vars: 1,
+            args: vec![Type::function(vec![a.clone()], Type::Bool), list(&a)],
+            ret: list(&a),
+            build: Compiler::build_list_filter,
+        },
+        Generic {
+            name: "fold",
+            vars: 2,
+            args: vec![Type::function(vec![b.clone(), a.clone()], b.clone()), b.clone(), list(&a)],
+            ret: b.clone(),
+            build: Compiler::build_list_fold,
+        },
+        Generic {
+            name: "length",
+            vars: 1,
+            args: vec![list(&a)],
+            ret: Type::Int,
+            build: Compiler::build_list_length,
+        },
+        Generic {
+            name: "head",
+            vars: 1,
+            args: vec![list(&a)],
+            ret: a.clone(),
+            build: Compiler::build_list_head,
+        },
+        Generic {
+            name: "tail",
+            vars: 1,
+            args: vec![list(&a)],
+            ret: list(&a),
+            build: Compiler::build_list_tail,
+        },
+        Generic {
+            name: "append",
+            vars: 1,
+            args: vec![list(&a), list(&a)],
+            ret: list(&a),
+            build: Compiler::build_list_append,
+        },
+    ]
+}
+
+/// Types of the functions every program can use without defining them.
+pub fn builtins() -> Vec<(&'static str, Scheme)> {
+    let externs = externs()
+        .into_iter()
+        .map(|f| (f.name, Scheme::mono(Type::function(f.args, f.ret))));
+
+    let intrinsics = intrinsics()
+        .into_iter()
+        .map(|f| (f.name, Scheme::mono(Type::function(f.args, f.ret))));
+
+    let generics = generics()
+        .into_iter()
+        .map(|f| (f.name, f.scheme()));
+
+    externs.chain(intrinsics).chain(generics).collect()
+}
 
 impl Compiler {
     pub(crate) fn init_std(&mut self) {
-        self.env.defs.insert("print_number".to_string(),
-            self.module.function_prototype(
-                Some(self.pool.intern("print_number")),
-                Context::function_type(
-                    self.context.void_type(),
-                    &[self.context.f64_type()],
-                    false),
-            )
-        );
+        for f in externs().into_iter().chain(runtime()) {
+            let function = self.declare_builtin(f.name, &f.args, &f.ret);
+            self.env.defs.insert(f.name.to_string(), function);
+        }
+
+        for f in intrinsics() {
+            let mut function = self.declare_builtin(f.name, &f.args, &f.ret);
+
+            let bb = BasicBlock::new(self.pool.intern("entry"), &mut function);
+            self.builder.position_at_end(&bb);
+
+            let ret = (f.build)(self, &function.params());
+            self.builder.build_ret(&ret);
+
+            self.env.defs.insert(f.name.to_string(), function);
+        }
+    }
+
+    /// Calls a builtin from generated code, used for operations implemented by the runtime.
+    pub(super) fn build_builtin_call(&mut self, name: &str, args: &[AnyValue]) -> AnyValue {
+        let f = self.env.defs.get(name)
+            .expect("Builtins must be declared before any code is built.");
+
+        self.builder.build_call(f, args, None)
+            .expect("Builtins must be called with all of their arguments.")
+    }
+
+    /// Exits the program, used where an operation has no result like dividing by zero, taking the
+    /// head of an empty list or a match without a matching arm.
+    pub(super) fn build_panic(&mut self, message: &str) {
+        let message = self.module.add_global_string(message.as_bytes(), Some(self.pool.intern("str")));
+        self.build_builtin_call("degu_panic", &[message]);
+        self.builder.build_unreachable();
+    }
+
+    /// Exits the program for NaN, the infinities and values out of the range of an int, `fptosi`
+    /// gives poison for them.
+    fn build_int_of_float(&mut self, args: &[AnyValue]) -> AnyValue {
+        let f64_type = self.context.f64_type();
+        let i64_type = self.context.i64_type();
+        let mut f = self.builder.get_insert_block().parent();
+
+        let below_max_block = BasicBlock::new(self.pool.intern("belowmax"), &mut f);
+        let panic_block = BasicBlock::new(self.pool.intern("outofrange"), &mut f);
+        let convert_block = BasicBlock::new(self.pool.intern("convert"), &mut f);
+
+        // Ordered comparisons are false for NaN. The bounds are -2^63 and 2^63 which are exact
+        // as floats unlike the largest int.
+        let min = self.builder.build_const_fp(f64_type, i64::MIN as f64);
+        let above_min = self.builder.build_fp_cmp(
+            RealPredicate::OGE,
+            &args[0],
+            &min,
+            Some(self.pool.intern("abovemin")));
+        self.builder.build_conditional_branch(&above_min, &below_max_block, &panic_block);
+
+        self.builder.position_at_end(&below_max_block);
+        let max = self.builder.build_const_fp(f64_type, -(i64::MIN as f64));
+        let below_max = self.builder.build_fp_cmp(
+            RealPredicate::OLT,
+            &args[0],
+            &max,
+            Some(self.pool.intern("belowmax")));
+        self.builder.build_conditional_branch(&below_max, &convert_block, &panic_block);
+
+        self.builder.position_at_end(&panic_block);
+        self.build_panic("int_of_float: the value does not fit in an int");
+
+        self.builder.position_at_end(&convert_block);
+        self.builder.build_cast_fp_to_int(&args[0], i64_type, None)
+    }
+
+    /// Name of the instance of a polymorphic builtin where its type variables are `types`.
+    /// The instance is generated the first time it is used.
+    pub(super) fn build_generic_instance(
+        &mut self,
+        name: &str,
+        types: &[Type]) -> Result<String, CompilerError>
+    {
+        let instance = instance_name(name, types);
+
+        if self.env.defs.contains_key(&instance) {
+            return Ok(instance);
+        }
+
+        let generic = generics()
+            .into_iter()
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("Cannot find generic {} function.", name));
+
+        let ty = generic.scheme().instantiate(types);
+        let (args, ret) = ty.split_function(generic.args.len())
+            .expect("Generic functions must take all of their arguments.");
+
+        let fn_type = {
+            let args = args.iter().map(|ty| self.llvm_type(ty)).collect::<Vec<_>>();
+            Context::function_type(self.llvm_type(&ret), &args, false)
+        };
+        let mut function = self.module.function_prototype(Some(self.pool.intern(&instance)), fn_type);
+
+        // Declared before the body is built so it can call itself.
+        self.env.defs.insert(instance.clone(), function.clone());
+        self.instances.insert(instance.clone(), ty);
+
+        // Instances can be generated in the middle of another function or at the top level.
+        let insert_block = if self.env.is_top_level() {
+            None
+        } else {
+            Some(self.builder.get_insert_block())
+        };
+        let locals = self.env.enter_function();
+        self.env.push_scope();
+
+        let bb = BasicBlock::new(self.pool.intern("entry"), &mut function);
+        self.builder.position_at_end(&bb);
+        (generic.build)(self, &function.params(), types);
+
+        self.env.pop_scope();
+        self.env.leave_function(locals);
+
+        match insert_block {
+            Some(block) => self.builder.position_at_end(&block),
+            None => self.builder.clear_insertion_position(),
+        }
+
+        self.finish_function(&mut function)?;
+
+        Ok(instance)
+    }
+
+    /// Builtins returning unit are declared as returning void to match C.
+    fn declare_builtin(&mut self, name: &str, args: &[Type], ret: &Type) -> Function {
+        let ret = match ret {
+            Type::Unit => self.context.void_type(),
+            ty => self.llvm_type(ty),
+        };
+        let args = args.iter().map(|ty| self.llvm_type(ty)).collect::<Vec<_>>();
+
+        self.module.function_prototype(
+            Some(self.pool.intern(name)),
+            Context::function_type(ret, &args, false),
+        )
     }
 }
 
 impl Runtime {
     pub(crate) fn init_std(&mut self) {
-        let print_number_f = self.env.defs.get("print_number")
-            .expect("Cannot find print_number function.");
+        for f in externs().into_iter().chain(runtime()) {
+            let function = self.env.defs.get(f.name)
+                .unwrap_or_else(|| panic!("Cannot find {} function.", f.name));
 
-        unsafe {
-            self.ee.add_global_mapping(print_number_f.as_value(), print_number as usize);
-        }
-    }
+            unsafe {
+                self.ee.add_global_mapping(function.as_value(), f.address);
+            }
+        }    }
+}
+
+/// Reports an error the program cannot recover from and exits.
+#[no_mangle]
+extern fn degu_panic(message: *const c_char) {
+    eprintln!("panic: {}", unsafe { CStr::from_ptr(message) }.to_string_lossy());
+    process::exit(1);
 }
 
 #[no_mangle]
 extern fn print_number(value: f64) {
     println!("{}", value);
 }
+
+#[no_mangle]
+extern fn print_int(value: i64) {
+    println!("{}", value);
+}
+
+#[no_mangle]
+extern fn print_char(value: u32) {
+    println!("{}", std::char::from_u32(value).unwrap_or(std::char::REPLACEMENT_CHARACTER));
+}
+
+/// Exits the program when the value is not a Unicode scalar value.
+#[no_mangle]
+extern fn char_of_int(value: i64) -> u32 {
+    if value < 0 || value > u32::max_value() as i64 || std::char::from_u32(value as u32).is_none() {
+        eprintln!("char_of_int: {} is not a valid Unicode scalar value", value);
+        process::exit(1);
+    }
+
+    value as u32
+}
+
+/// `'\0'` results in an empty string because strings are NUL terminated.
+#[no_mangle]
+extern fn string_of_char(value: u32) -> *mut c_char {
+    let c = std::char::from_u32(value).unwrap_or(std::char::REPLACEMENT_CHARACTER);
+
+    CString::new(c.to_string())
+        .unwrap_or_default()
+        .into_raw()
+}
+
+/// Exits the program unless the string holds exactly one Unicode scalar value.
+#[no_mangle]
+extern fn char_of_string(value: *const c_char) -> u32 {
+    let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
+    let mut chars = value.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c as u32,
+        _ => {
+            let message = "char_of_string: the string must hold exactly one character\0";
+            degu_panic(message.as_ptr() as *const c_char);
+            unreachable!()
+        },
+    }
+}
+
+#[no_mangle]
+extern fn print_string(value: *const c_char) {
+    println!("{}", unsafe { CStr::from_ptr(value) }.to_string_lossy());
+}
+
+/// The result is never freed since there is no memory management yet.
+#[no_mangle]
+extern fn string_concat(a: *const c_char, b: *const c_char) -> *mut c_char {
+    let (a, b) = unsafe { (CStr::from_ptr(a), CStr::from_ptr(b)) };
+    let bytes = [a.to_bytes(), b.to_bytes()].concat();
+
+    CString::new(bytes)
+        .expect("Strings cannot contain NUL characters.")
+        .into_raw()
+}
+
+/// Length in Unicode scalar values, UTF-8 continuation bytes are not counted.
+#[no_mangle]
+extern fn string_length(value: *const c_char) -> i64 {
+    let bytes = unsafe { CStr::from_ptr(value) }.to_bytes();
+    bytes.iter().filter(|&&byte| byte & 0xC0 != 0x80).count() as i64
+}
+
+/// Compares the UTF-8 bytes which is the same as comparing the scalar values.
+#[no_mangle]
+extern fn string_compare(a: *const c_char, b: *const c_char) -> i64 {
+    let (a, b) = unsafe { (CStr::from_ptr(a), CStr::from_ptr(b)) };
+    a.to_bytes().cmp(b.to_bytes()) as i64
+}
@@ -0,0 +1,131 @@
+//! Tagged unions.
+//!
+//! A union is a pointer to the named struct of its type which only has the `i32` tag. The value
+//! behind it is the struct of its constructor `{ i32 tag, fields... }`. Constructors with fields
+//! are top-level functions allocating the value on the heap, the ones without fields are
+//! constant globals.
+
+use llvm_wrap::prelude::*;
+
+use std::iter;
+
+use crate::typeck::{self, TypeDecl};
+
+use super::{Compiler, CompilerError};
+
+impl Compiler {
+    /// Creates the structs of the constructors and builds their values or functions.
+    pub(super) fn build_constructors(&mut self) -> Result<(), CompilerError> {
+        let mut unions = self.types.decls()
+            .iter()
+            .filter_map(|(name, decl)| match decl {
+                TypeDecl::Union(cases) => Some((name.clone(), cases.clone())),
+                TypeDecl::Record(_) => None,
+            })
+            .collect::<Vec<_>>();
+        unions.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (union, cases) in &unions {
+            for (case, fields) in cases {
+                let field_types = iter::once(self.context.i32_type())
+                    .chain(fields.iter().map(|ty| self.llvm_type(ty)))
+                    .collect::<Vec<_>>();
+
+                let case_name = format!("{}.{}", union, case);
+                let case_type = self.context.named_struct_type(self.pool.intern(&case_name));
+                case_type.set_struct_body(&field_types, false);
+                self.case_types.insert(case.clone(), case_type);
+
+                if fields.is_empty() {
+                    let value = self.build_union_value(case, &[]);
+                    self.env.bind(case.clone(), value);
+                } else {
+                    self.build_constructor(union, case, fields)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The function of a constructor takes its fields and returns the union.
+    fn build_constructor(
+        &mut self,
+        union: &String,
+        case: &String,
+        fields: &[typeck::Type]) -> Result<(), CompilerError>
+    {
+        let function_type = {
+            let ret_type = self.llvm_type(&typeck::Type::Named(union.clone()));
+            let arg_types = fields.iter().map(|ty| self.llvm_type(ty)).collect::<Vec<_>>();
+            Context::function_type(ret_type, &arg_types, false)
+        };
+
+        let mut f = self.module.function_prototype(Some(self.pool.intern(case.as_ref())), function_type);
+
+        let bb = BasicBlock::new(self.pool.intern("entry"), &mut f);
+        self.builder.position_at_end(&bb);
+
+        // Inside of the function the value is allocated instead of being a constant.
+        self.env.push_scope();
+        let value = self.build_union_value(case, &f.params());
+        self.env.pop_scope();
+
+        self.builder.build_ret(&value);
+        self.builder.clear_insertion_position();
+
+        self.finish_function(&mut f)?;
+        self.env.defs.insert(case.clone(), f);
+
+        Ok(())
+    }
+
+    /// Allocates the value of a constructor with the values of its fields.
+    fn build_union_value(&mut self, case: &str, values: &[AnyValue]) -> AnyValue {
+        let (union_type, tag) = {
+            let (union, tag, _) = self.types.constructor(case)
+                .expect("Constructors must be declared before they are used.");
+            (Context::pointer_type(self.named_types[union]), tag)
+        };
+        let case_type = self.case_types[case];
+
+        let values = iter::once(self.builder.build_const_int(self.context.i32_type(), tag as u64, false))
+            .chain(values.iter().cloned())
+            .collect::<Vec<_>>();
+
+        // Top-level values are constant folded.
+        if self.env.is_top_level() {
+            let value = Context::const_named_struct(case_type, &values);
+            let value = self.module.add_global_constant(&value, Some(self.pool.intern(case)));
+            return self.builder.build_bitcast(&value, union_type, None);
+        }
+
+        let ptr = self.builder.build_malloc(case_type, Some(self.pool.intern(case)));
+
+        for (index, value) in values.iter().enumerate() {
+            let field = self.builder.build_struct_gep(&ptr, index as u32, None);
+            self.builder.build_store(value, &field);
+        }
+
+        self.builder.build_bitcast(&ptr, union_type, Some(self.pool.intern("union")))
+    }
+
+    /// Loads the fields of a union value built by the constructor `case`.
+    pub(super) fn build_case_fields(&mut self, case: &str, value: &AnyValue) -> Vec<(AnyValue, typeck::Type)> {
+        let (_, _, fields) = self.types.constructor(case)
+            .expect("Constructors must be declared before they are used.");
+        let fields = fields.to_vec();
+
+        let case_type = Context::pointer_type(self.case_types[case]);
+        let ptr = self.builder.build_bitcast(value, case_type, Some(self.pool.intern(case)));
+
+        fields
+            .into_iter()
+            .enumerate()
+            .map(|(index, ty)| {
+                let field = self.builder.build_struct_gep(&ptr, index as u32 + 1, None);
+                (self.builder.build_load(&field, None), ty)
+            })
+            .collect()
+    }
+}
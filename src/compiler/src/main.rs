@@ -1,50 +1,195 @@
+mod cli;
 mod compiler;
+mod typeck;
 
 use lexpar::lexer::Span;
 
+use llvm_wrap::execution_engine::CodeGenFileType;
+
+use syntax::diagnostics::Diagnostic;
 use syntax::lexer;
 use syntax::parser::Parser;
 use syntax::parser::ast::{Ast, AstNode};
 
-use std::fs::File;
+use std::env;
+use std::fs::{self, File};
 use std::io::BufReader;
 use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+use std::process;
 
+use self::cli::{Command, Emit, Options};
 use self::compiler::Compiler;
 
 fn read_file(name: &str) -> io::Result<String> {
-    let file = File::open(name)?;
-    let mut buf_reader = BufReader::new(file);
     let mut contents = String::new();
-    buf_reader.read_to_string(&mut contents)?;
+
+    if name == "-" {
+        io::stdin().read_to_string(&mut contents)?;
+    } else {
+        let file = File::open(name)?;
+        let mut buf_reader = BufReader::new(file);
+        buf_reader.read_to_string(&mut contents)?;
+    }
+
     Ok(contents)
 }
 
-fn run(source: &str) {
-    let lexer = lexer::lexer();
-    let iter = lexer.src_iter(source);
-    let ast = Parser::parse(iter).unwrap();
-    // println!("== ast ==\n\n{:?}\n", ast);
+fn write_output(path: &str, contents: &str) -> io::Result<()> {
+    if path == "-" {
+        io::stdout().write_all(contents.as_bytes())
+    } else {
+        fs::write(path, contents)
+    }
+}
+
+fn write_error(path: &str, err: io::Error) -> Diagnostic {
+    Diagnostic::error(format!("Cannot write {}: {}", path, err))
+}
+
+/// Absolute path with the symbolic links resolved. Files that do not exist yet are resolved
+/// through their directory.
+fn canonical_path(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    Some(dir.canonicalize().ok()?.join(path.file_name()?))
+}
+
+/// Makes sure none of the outputs would overwrite the input, before anything is written.
+fn check_outputs(options: &Options, name: &str) -> Result<(), Diagnostic> {
+    let input = match canonical_path(name) {
+        Some(input) if name != "-" => input,
+        _ => return Ok(()),
+    };
+
+    for &emit in &options.emit {
+        let path = options.output_path(name, emit);
+
+        if path != "-" && canonical_path(&path).as_ref() == Some(&input) {
+            return Err(Diagnostic::error(format!(
+                "Refusing to overwrite the input {} with the {:?} output",
+                name,
+                emit)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Compiles a file, the problems found are rendered with the lines of the source they are on.
+fn process_file(options: &Options, name: &str) -> Result<(), String> {
+    let source = read_file(name).map_err(|err| {
+        Diagnostic::error(format!("Cannot read {}: {}", name, err)).render(name, "")
+    })?;
 
-    let mut compiler = Compiler::new();
+    process_source(options, name, &source).map_err(|diagnostics| {
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(name, &source))
+            .collect()
+    })
+}
 
-    let ast = AstNode::new(Span::new(0, source.len(), 0), Ast::Block(ast));
+fn process_source(options: &Options, name: &str, source: &str) -> Result<(), Vec<Diagnostic>> {
+    check_outputs(options, name).map_err(|diagnostic| vec![diagnostic])?;
+
+    let lexer = lexer::lexer();
+    let (ast, errors) = Parser::parse(lexer.src_iter(source));
 
-    compiler.compile(&ast).unwrap();
-    println!("== llvm ir ==\n\n{:?}", compiler.module());
+    // Written even with syntax errors, the broken items are error nodes.
+    if options.emit.contains(&Emit::Ast) {
+        let path = options.output_path(name, Emit::Ast);
+        write_output(&path, &format!("{:#?}\n", ast))
+            .map_err(|err| vec![write_error(&path, err)])?;
+    }
 
-    println!("== runtime ==\n");
-    let mut runtime = compiler.into_runtime();
-    runtime.run_main();
+    // Every syntax error of the file is reported at once.
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(Diagnostic::from).collect());
+    }
+
+    compile_ast(options, name, source, ast).map_err(|diagnostic| vec![diagnostic])
 }
 
-fn main() -> io::Result<()> {
-    let name = "main.dg";
-    let source = read_file(name)?;
+fn compile_ast(
+    options: &Options,
+    name: &str,
+    source: &str,
+    ast: Vec<AstNode>) -> Result<(), Diagnostic>
+{
+    let mut ast = AstNode::new(Span::new(0, source.len(), 0), Ast::Block(ast));
+    ast.number_nodes();
+
+    let types = typeck::check(&ast, compiler::builtins())?;
+
+    if !options.quiet {
+        for warning in types.warnings() {
+            eprint!("{}", warning.render(name, source));
+        }
+    }
+
+    let mut compiler = Compiler::new(options.opt_level);
+    compiler.compile(&ast, types)?;
 
-    println!("== {} ==\n\n{}", name, source);
+    for &emit in &options.emit {
+        let path = options.output_path(name, emit);
 
-    run(&source);
+        match emit {
+            Emit::Ast => continue,
+            Emit::Ir => write_output(&path, &format!("{:?}", compiler.module()))
+                .map_err(|err| write_error(&path, err))?,
+            Emit::Bitcode => compiler.emit_bitcode(&path)?,
+            Emit::Assembly => compiler.emit_native(&path, CodeGenFileType::Assembly)?,
+            Emit::Object => compiler.emit_native(&path, CodeGenFileType::Object)?,
+            Emit::Executable => compiler.emit_executable(&path)?,
+        }
+
+        if !options.quiet && path != "-" {
+            eprintln!("Wrote {}", path);
+        }
+    }
+
+    if options.command == Command::Run {
+        let mut runtime = compiler.into_runtime();
+        runtime.run_main()?;
+    }
 
     Ok(())
 }
+
+fn main() {
+    let options = match cli::parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, cli::USAGE);
+            process::exit(2);
+        },
+    };
+
+    if options.help {
+        print!("{}", cli::USAGE);
+        return;
+    }
+
+    let mut failed = false;
+
+    for name in &options.inputs {
+        if let Err(report) = process_file(&options, name) {
+            eprint!("{}", report);
+            failed = true;
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,271 @@
+//! Type declarations and the expressions using the declared types.
+
+use lexpar::lexer::Span;
+
+use syntax::parser::ast::{Ast, AstNode, TypeDef, TypeExpr};
+
+use super::{InferResult, Type, TypeChecker, TypeDecl, TypeError};
+
+/// Names of the types every program has.
+const BUILTIN_TYPES: &[&str] = &["unit", "bool", "int", "float", "char", "string"];
+
+impl TypeChecker {
+    /// Declares the types of the top level. All names are known before any of the definitions
+    /// are resolved so types can refer to each other regardless of the declaration order.
+    pub(super) fn declare_types(&mut self, items: &[AstNode]) -> Result<(), TypeError> {
+        for item in items {
+            if let Ast::Type { name, .. } = &*item.expr {
+                if BUILTIN_TYPES.contains(&name.as_str()) || self.decls.contains_key(name) {
+                    return Err(TypeError {
+                        message: format!("Type `{}` is already defined", name),
+                        span: item.span.clone(),
+                    });
+                }
+
+                // Placeholder until the definitions are resolved.
+                self.decls.insert(name.clone(), TypeDecl::Record(Vec::new()));
+            }
+        }
+
+        for item in items {
+            if let Ast::Type { name, def } = &*item.expr {
+                let decl = self.resolve_type_def(&item.span, def)?;
+                self.define_type(&item.span, name, decl)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn define_type(&mut self, span: &Span, name: &String, decl: TypeDecl) -> Result<(), TypeError> {
+        match &decl {
+            TypeDecl::Record(fields) => {
+                for (field, _) in fields {
+                    self.field_records.insert(field.clone(), name.clone());
+                }
+            },
+            TypeDecl::Union(cases) => {
+                // Constructors are top-level functions, or values when they have no fields.
+                for (case, fields) in cases {
+                    if self.lookup(case).is_some() {
+                        return Err(TypeError {
+                            message: format!("Constructor {:?} is already defined", case),
+                            span: span.clone(),
+                        });
+                    }
+
+                    let ty = Type::function(fields.clone(), Type::Named(name.clone()));
+                    if !fields.is_empty() {
+                        self.defs.insert(case.clone(), ty.clone());
+                    }
+
+                    self.bind(case.clone(), ty);
+                    self.constructors.insert(case.clone(), name.clone());
+                }
+            },
+        }
+
+        self.decls.insert(name.clone(), decl);
+
+        Ok(())
+    }
+
+    fn resolve_type_def(&self, span: &Span, def: &TypeDef) -> Result<TypeDecl, TypeError> {
+        match def {
+            TypeDef::Record(field_defs) => {
+                let mut fields: Vec<(String, Type)> = Vec::with_capacity(field_defs.len());
+
+                for field in field_defs {
+                    if fields.iter().any(|(name, _)| *name == field.name) {
+                        return Err(TypeError {
+                            message: format!("Field {:?} is declared more than once", field.name),
+                            span: span.clone(),
+                        });
+                    }
+
+                    fields.push((field.name.clone(), self.resolve_type(span, &field.ty)?));
+                }
+
+                Ok(TypeDecl::Record(fields))
+            },
+            TypeDef::Union(case_defs) => {
+                let mut cases: Vec<(String, Vec<Type>)> = Vec::with_capacity(case_defs.len());
+
+                for case in case_defs {
+                    if cases.iter().any(|(name, _)| *name == case.name) {
+                        return Err(TypeError {
+                            message: format!("Constructor {:?} is declared more than once", case.name),
+                            span: span.clone(),
+                        });
+                    }
+
+                    let fields = case.fields
+                        .iter()
+                        .map(|field| self.resolve_type(span, field))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    cases.push((case.name.clone(), fields));
+                }
+
+                Ok(TypeDecl::Union(cases))
+            },
+        }
+    }
+
+    /// The type a type expression written at `span` stands for.
+    pub(super) fn resolve_type(&self, span: &Span, ty: &TypeExpr) -> InferResult {
+        match ty {
+            TypeExpr::Named(name) => match name.as_str() {
+                "unit" => Ok(Type::Unit),
+                "bool" => Ok(Type::Bool),
+                "int" => Ok(Type::Int),
+                "float" => Ok(Type::Float),
+                "char" => Ok(Type::Char),
+                "string" => Ok(Type::String),
+                _ if self.decls.contains_key(name) => Ok(Type::Named(name.clone())),
+                _ => Err(TypeError {
+                    message: format!("Unknown type `{}`", name),
+                    span: span.clone(),
+                }),
+            },
+            TypeExpr::Applied(arg, name) => match name.as_str() {
+                "list" => Ok(Type::List(Box::new(self.resolve_type(span, arg)?))),
+                _ => Err(TypeError {
+                    message: format!("Unknown type constructor `{}`", name),
+                    span: span.clone(),
+                }),
+            },
+            TypeExpr::Tuple(items) => items
+                .iter()
+                .map(|item| self.resolve_type(span, item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Type::Tuple),
+            TypeExpr::Fn(arg, ret) => Ok(Type::Fn(
+                Box::new(self.resolve_type(span, arg)?),
+                Box::new(self.resolve_type(span, ret)?))),
+        }
+    }
+
+    pub(super) fn infer_type_decl(&mut self, span: &Span) -> InferResult {
+        if !self.is_top_level() {
+            return Err(TypeError {
+                message: "Types can only be declared at the top level".to_string(),
+                span: span.clone(),
+            });
+        }
+
+        // Already declared by `declare_types`.
+        Ok(Type::Unit)
+    }
+
+    pub(super) fn infer_record(&mut self, span: &Span, fields: &Vec<(String, AstNode)>) -> InferResult {
+        let (first, _) = &fields[0];
+        let record = self.field_records.get(first)
+            .cloned()
+            .ok_or_else(|| TypeError {
+                message: format!("No record has a field {:?}", first),
+                span: span.clone(),
+            })?;
+
+        self.infer_fields(span, &record, fields)?;
+
+        let missing = self.fields_of(&record)
+            .into_iter()
+            .find(|(name, _)| fields.iter().all(|(field, _)| field != name));
+
+        if let Some((name, _)) = missing {
+            return Err(TypeError {
+                message: format!("Field {:?} of record `{}` is not set", name, record),
+                span: span.clone(),
+            });
+        }
+
+        Ok(Type::Named(record))
+    }
+
+    pub(super) fn infer_field(&mut self, span: &Span, expr: &AstNode, field: &String) -> InferResult {
+        let ty = self.infer(expr)?;
+        let record = self.record_of(span, &ty, field)?;
+
+        self.field_type(span, &record, field)
+    }
+
+    pub(super) fn infer_update(
+        &mut self,
+        span: &Span,
+        record: &AstNode,
+        fields: &Vec<(String, AstNode)>) -> InferResult
+    {
+        let ty = self.infer(record)?;
+        let (first, _) = &fields[0];
+        let name = self.record_of(span, &ty, first)?;
+
+        self.infer_fields(span, &name, fields)?;
+
+        Ok(ty)
+    }
+
+    /// The record a value of type `ty` is when `field` is used on it.
+    /// Unknown types are inferred from the field name.
+    fn record_of(&mut self, span: &Span, ty: &Type, field: &String) -> Result<String, TypeError> {
+        match self.resolve(ty) {
+            Type::Named(name) => Ok(name),
+            Type::Var(_) => {
+                let record = self.field_records.get(field)
+                    .cloned()
+                    .ok_or_else(|| TypeError {
+                        message: format!("No record has a field {:?}", field),
+                        span: span.clone(),
+                    })?;
+
+                self.expect(span, &Type::Named(record.clone()), ty)?;
+                Ok(record)
+            },
+            ty => Err(TypeError {
+                message: format!("Type `{}` has no field {:?}", self.apply(&ty), field),
+                span: span.clone(),
+            }),
+        }
+    }
+
+    /// Checks the values given to fields of the record, each field can be given once.
+    fn infer_fields(
+        &mut self,
+        span: &Span,
+        record: &String,
+        fields: &Vec<(String, AstNode)>) -> Result<(), TypeError>
+    {
+        for (index, (field, value)) in fields.iter().enumerate() {
+            if fields[..index].iter().any(|(other, _)| other == field) {
+                return Err(TypeError {
+                    message: format!("Field {:?} is set more than once", field),
+                    span: span.clone(),
+                });
+            }
+
+            let expected = self.field_type(span, record, field)?;
+            let ty = self.infer(value)?;
+            self.expect(&value.span, &expected, &ty)?;
+        }
+
+        Ok(())
+    }
+
+    fn field_type(&self, span: &Span, record: &String, field: &String) -> InferResult {
+        self.fields_of(record)
+            .into_iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, ty)| ty)
+            .ok_or_else(|| TypeError {
+                message: format!("Type `{}` has no field {:?}", record, field),
+                span: span.clone(),
+            })
+    }
+
+    fn fields_of(&self, name: &str) -> Vec<(String, Type)> {
+        match self.decls.get(name) {
+            Some(TypeDecl::Record(fields)) => fields.clone(),
+            Some(TypeDecl::Union(_)) | None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Patterns and `match` expressions.
+//!
+//! Once the arms of a `match` are typed they are checked with the usefulness algorithm from
+//! "Warnings for pattern matching" by Luc Maranget. An arm is redundant when the arms before it
+//! match every value it does and the match is incomplete when some value is matched by no arm.
+
+use lexpar::lexer::Span;
+
+use syntax::diagnostics::{Diagnostic, codes};
+use syntax::parser::ast::{AstNode, Literal, MatchArm, Pattern};
+
+use std::fmt;
+use std::iter;
+
+use super::{InferResult, Type, TypeChecker, TypeDecl, TypeError};
+
+/// How the analysis sees the values a pattern tests for.
+#[derive(Clone, Debug, PartialEq)]
+enum Ctor {
+    Tuple(usize),
+    Bool(bool),
+    /// The other literals, their types have too many values to list.
+    Literal(Literal),
+    Nil,
+    Cons,
+    /// Case of a union with the number of its fields.
+    Case(String, usize),
+}
+
+impl Ctor {
+    fn arity(&self) -> usize {
+        match self {
+            Ctor::Tuple(arity) | Ctor::Case(_, arity) => *arity,
+            Ctor::Cons => 2,
+            Ctor::Bool(_) | Ctor::Literal(_) | Ctor::Nil => 0,
+        }
+    }
+}
+
+/// Pattern without names, list literals are written with `::` and `[]`.
+#[derive(Clone, Debug)]
+enum Pat {
+    Any,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+impl Pat {
+    /// Whether it needs parentheses as a field of a constructor.
+    fn is_compound(&self) -> bool {
+        match self {
+            Pat::Ctor(Ctor::Cons, _) => true,
+            Pat::Ctor(Ctor::Case(_, arity), _) => *arity > 0,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pat::Any => write!(f, "_"),
+            Pat::Ctor(Ctor::Tuple(_), items) => {
+                write!(f, "(")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, ")")
+            },
+            Pat::Ctor(Ctor::Bool(value), _) => write!(f, "{}", value),
+            Pat::Ctor(Ctor::Literal(literal), _) => match literal {
+                Literal::Integer(value) => write!(f, "{}", value),
+                Literal::Float(value) => write!(f, "{:?}", value),
+                Literal::Bool(value) => write!(f, "{}", value),
+                Literal::Char(value) => write!(f, "{:?}", value),
+                Literal::Str(value) => write!(f, "{:?}", value),
+            },
+            Pat::Ctor(Ctor::Nil, _) => write!(f, "[]"),
+            Pat::Ctor(Ctor::Cons, items) => match items[0] {
+                Pat::Ctor(Ctor::Cons, _) => write!(f, "({}) :: {}", items[0], items[1]),
+                _ => write!(f, "{} :: {}", items[0], items[1]),
+            },
+            Pat::Ctor(Ctor::Case(name, _), fields) => {
+                write!(f, "{}", name)?;
+                for field in fields {
+                    if field.is_compound() {
+                        write!(f, " ({})", field)?;
+                    } else {
+                        write!(f, " {}", field)?;
+                    }
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Rows of patterns, the columns are the parts of the value being matched.
+type Matrix = Vec<Vec<Pat>>;
+
+/// Rows that can match once the first part of the value is known to be `ctor`, with its fields
+/// in place of the first column.
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor) -> Matrix {
+    rows.iter()
+        .filter_map(|row| {
+            let fields = match &row[0] {
+                Pat::Any => vec![Pat::Any; ctor.arity()],
+                Pat::Ctor(other, fields) if other == ctor => fields.clone(),
+                Pat::Ctor(..) => return None,
+            };
+
+            Some(fields.into_iter().chain(row[1..].iter().cloned()).collect())
+        })
+        .collect()
+}
+
+/// Rows that can match when the first part of the value is none of the listed constructors.
+fn default(rows: &[Vec<Pat>]) -> Matrix {
+    rows.iter()
+        .filter_map(|row| match row[0] {
+            Pat::Any => Some(row[1..].to_vec()),
+            Pat::Ctor(..) => None,
+        })
+        .collect()
+}
+
+/// The constructors the first column tests for in the order they appear.
+fn head_ctors(rows: &[Vec<Pat>]) -> Vec<Ctor> {
+    let mut ctors: Vec<Ctor> = Vec::new();
+
+    for row in rows {
+        if let Pat::Ctor(ctor, _) = &row[0] {
+            if !ctors.contains(ctor) {
+                ctors.push(ctor.clone());
+            }
+        }
+    }
+
+    ctors
+}
+
+impl TypeChecker {
+    pub(super) fn infer_match(&mut self, span: &Span, expr: &AstNode, arms: &Vec<MatchArm>) -> InferResult {
+        let ty = self.infer(expr)?;
+        let result = self.fresh();
+
+        for arm in arms {
+            self.check_bindings(&arm.span, &arm.pattern, &mut Vec::new())?;
+
+            self.push_scope();
+            let body = self.bind_pattern(&arm.span, &arm.pattern, &ty)
+                .and_then(|_| self.infer(&arm.body));
+            self.pop_scope();
+
+            self.expect(&arm.body.span, &result, &body?)?;
+        }
+
+        self.check_arms(span, arms);
+
+        Ok(result)
+    }
+
+    /// Binds the names of the pattern to the matching parts of a value of type `ty`.
+    pub(super) fn bind_pattern(&mut self, span: &Span, pattern: &Pattern, ty: &Type) -> Result<(), TypeError> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Ident(name) if self.constructors.contains_key(name) => {
+                self.bind_constructor(span, name, &[], ty)
+            },
+            Pattern::Ident(name) => {
+                self.bind(name.clone(), ty.clone());
+                Ok(())
+            },
+            Pattern::Tuple(items) if items.is_empty() => self.expect(span, &Type::Unit, ty),
+            Pattern::Tuple(items) => {
+                let types = items.iter().map(|_| self.fresh()).collect::<Vec<_>>();
+                self.expect(span, &Type::Tuple(types.clone()), ty)?;
+
+                items
+                    .iter()
+                    .zip(&types)
+                    .map(|(item, ty)| self.bind_pattern(span, item, ty))
+                    .collect()
+            },
+            Pattern::Literal(literal) => {
+                let literal_type = match literal {
+                    Literal::Integer(_) => Type::Int,
+                    Literal::Float(_) => Type::Float,
+                    Literal::Bool(_) => Type::Bool,
+                    Literal::Char(_) => Type::Char,
+                    Literal::Str(_) => Type::String,
+                };
+
+                self.expect(span, &literal_type, ty)
+            },
+            Pattern::List(items) => {
+                let item = self.fresh();
+                self.expect(span, &Type::List(Box::new(item.clone())), ty)?;
+
+                items
+                    .iter()
+                    .map(|pattern| self.bind_pattern(span, pattern, &item))
+                    .collect()
+            },
+            Pattern::Cons(head, tail) => {
+                let item = self.fresh();
+                self.expect(span, &Type::List(Box::new(item.clone())), ty)?;
+
+                self.bind_pattern(span, head, &item)?;
+                self.bind_pattern(span, tail, ty)
+            },
+            Pattern::Constructor(name, fields) => self.bind_constructor(span, name, fields, ty),
+        }
+    }
+
+    fn bind_constructor(
+        &mut self,
+        span: &Span,
+        name: &String,
+        fields: &[Pattern],
+        ty: &Type) -> Result<(), TypeError>
+    {
+        let union = self.constructors.get(name)
+            .cloned()
+            .ok_or_else(|| TypeError {
+                message: format!("Unknown constructor {:?}", name),
+                span: span.clone(),
+            })?;
+
+        let field_types = match &self.decls[&union] {
+            TypeDecl::Union(cases) => cases
+                .iter()
+                .find(|(case, _)| case == name)
+                .map(|(_, fields)| fields.clone())
+                .expect("Constructors must belong to their union."),
+            TypeDecl::Record(_) => unreachable!("Constructors must belong to a union."),
+        };
+
+        if fields.len() != field_types.len() {
+            return Err(TypeError {
+                message: format!(
+                    "Constructor {:?} has {} field(s) but the pattern has {}",
+                    name,
+                    field_types.len(),
+                    fields.len()),
+                span: span.clone(),
+            });
+        }
+
+        self.expect(span, &Type::Named(union), ty)?;
+
+        fields
+            .iter()
+            .zip(&field_types)
+            .map(|(field, ty)| self.bind_pattern(span, field, ty))
+            .collect()
+    }
+
+    /// Each name can be bound once in a pattern.
+    pub(super) fn check_bindings<'a>(
+        &self,
+        span: &Span,
+        pattern: &'a Pattern,
+        names: &mut Vec<&'a str>) -> Result<(), TypeError>
+    {
+        match pattern {
+            Pattern::Wildcard | Pattern::Literal(_) => Ok(()),
+            Pattern::Ident(name) if self.constructors.contains_key(name) => Ok(()),
+            Pattern::Ident(name) => {
+                if names.contains(&name.as_str()) {
+                    return Err(TypeError {
+                        message: format!("Variable {:?} is bound more than once in the pattern", name),
+                        span: span.clone(),
+                    });
+                }
+
+                names.push(name);
+                Ok(())
+            },
+            Pattern::Tuple(items) | Pattern::List(items) | Pattern::Constructor(_, items) => items
+                .iter()
+                .map(|item| self.check_bindings(span, item, names))
+                .collect(),
+            Pattern::Cons(head, tail) => {
+                self.check_bindings(span, head, names)?;
+                self.check_bindings(span, tail, names)
+            },
+        }
+    }
+
+    /// Whether the pattern matches every value of its type.
+    pub(super) fn is_irrefutable(&self, pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Wildcard => true,
+            Pattern::Ident(name) => !self.constructors.contains_key(name),
+            Pattern::Tuple(items) => items.iter().all(|item| self.is_irrefutable(item)),
+            Pattern::Literal(_) | Pattern::List(_) | Pattern::Cons(..) | Pattern::Constructor(..) => false,
+        }
+    }
+
+    /// Warns about the arms that are never matched and the values no arm matches.
+    fn check_arms(&mut self, span: &Span, arms: &Vec<MatchArm>) {
+        let mut rows = Matrix::new();
+
+        for arm in arms {
+            let row = vec![self.lower(&arm.pattern)];
+
+            if !self.is_useful(&rows, &row) {
+                self.warnings.push(
+                    Diagnostic::warning("This arm is never matched")
+                        .with_code(codes::UNREACHABLE_ARM)
+                        .with_label(arm.span.clone(), "the arms before match all of its values"));
+            }
+
+            rows.push(row);
+        }
+
+        if let Some(missing) = self.missing(&rows, 1) {
+            self.warnings.push(
+                Diagnostic::warning(format!("Incomplete match, `{}` is not matched", missing[0]))
+                    .with_code(codes::INCOMPLETE_MATCH)
+                    .with_label(span.clone(), "")
+                    .with_note("matching a value no arm matches stops the program"));
+        }
+    }
+
+    fn lower(&self, pattern: &Pattern) -> Pat {
+        match pattern {
+            Pattern::Wildcard => Pat::Any,
+            Pattern::Ident(name) if self.constructors.contains_key(name) => {
+                Pat::Ctor(Ctor::Case(name.clone(), 0), Vec::new())
+            },
+            Pattern::Ident(_) => Pat::Any,
+            Pattern::Tuple(items) => Pat::Ctor(
+                Ctor::Tuple(items.len()),
+                items.iter().map(|item| self.lower(item)).collect()),
+            Pattern::Literal(Literal::Bool(value)) => Pat::Ctor(Ctor::Bool(*value), Vec::new()),
+            Pattern::Literal(literal) => Pat::Ctor(Ctor::Literal(literal.clone()), Vec::new()),
+            Pattern::List(items) => items
+                .iter()
+                .rev()
+                .fold(Pat::Ctor(Ctor::Nil, Vec::new()), |tail, item| {
+                    Pat::Ctor(Ctor::Cons, vec![self.lower(item), tail])
+                }),
+            Pattern::Cons(head, tail) => Pat::Ctor(Ctor::Cons, vec![self.lower(head), self.lower(tail)]),
+            Pattern::Constructor(name, fields) => Pat::Ctor(
+                Ctor::Case(name.clone(), fields.len()),
+                fields.iter().map(|field| self.lower(field)).collect()),
+        }
+    }
+
+    /// Every constructor of the type `ctor` belongs to or `None` when there are too many.
+    fn signature(&self, ctor: &Ctor) -> Option<Vec<Ctor>> {
+        match ctor {
+            Ctor::Tuple(arity) => Some(vec![Ctor::Tuple(*arity)]),
+            Ctor::Bool(_) => Some(vec![Ctor::Bool(true), Ctor::Bool(false)]),
+            Ctor::Nil | Ctor::Cons => Some(vec![Ctor::Nil, Ctor::Cons]),
+            Ctor::Case(name, _) => match &self.decls[&self.constructors[name]] {
+                TypeDecl::Union(cases) => Some(cases
+                    .iter()
+                    .map(|(case, fields)| Ctor::Case(case.clone(), fields.len()))
+                    .collect()),
+                TypeDecl::Record(_) => unreachable!("Constructors must belong to a union."),
+            },
+            Ctor::Literal(_) => None,
+        }
+    }
+
+    /// The constructors of the first column when they are all of their type.
+    fn complete_signature(&self, rows: &[Vec<Pat>]) -> Option<Vec<Ctor>> {
+        let ctors = head_ctors(rows);
+
+        ctors.first()
+            .and_then(|ctor| self.signature(ctor))
+            .filter(|signature| signature.iter().all(|ctor| ctors.contains(ctor)))
+    }
+
+    /// Whether `row` matches a value none of the rows do.
+    fn is_useful(&self, rows: &[Vec<Pat>], row: &[Pat]) -> bool {
+        let (head, rest) = match row.split_first() {
+            Some(split) => split,
+            None => return rows.is_empty(),
+        };
+
+        match head {
+            Pat::Ctor(ctor, fields) => {
+                let row = fields.iter().chain(rest).cloned().collect::<Vec<_>>();
+                self.is_useful(&specialize(rows, ctor), &row)
+            },
+            Pat::Any => match self.complete_signature(rows) {
+                Some(signature) => signature.iter().any(|ctor| {
+                    let row = iter::repeat(Pat::Any)
+                        .take(ctor.arity())
+                        .chain(rest.iter().cloned())
+                        .collect::<Vec<_>>();
+                    self.is_useful(&specialize(rows, ctor), &row)
+                }),
+                None => self.is_useful(&default(rows), rest),
+            },
+        }
+    }
+
+    /// Patterns for the `width` parts of a value that none of the rows match.
+    fn missing(&self, rows: &[Vec<Pat>], width: usize) -> Option<Vec<Pat>> {
+        if width == 0 {
+            return if rows.is_empty() { Some(Vec::new()) } else { None };
+        }
+
+        if let Some(signature) = self.complete_signature(rows) {
+            return signature.into_iter().find_map(|ctor| {
+                let arity = ctor.arity();
+                let mut fields = self.missing(&specialize(rows, &ctor), arity + width - 1)?;
+                let rest = fields.split_off(arity);
+
+                Some(iter::once(Pat::Ctor(ctor, fields)).chain(rest).collect())
+            });
+        }
+
+        let mut missing = self.missing(&default(rows), width - 1)?;
+
+        // Name a constructor no row tests for when there is one.
+        let ctors = head_ctors(rows);
+        let head = ctors.first()
+            .and_then(|ctor| self.signature(ctor))
+            .and_then(|signature| signature.into_iter().find(|ctor| !ctors.contains(ctor)))
+            .map_or(Pat::Any, |ctor| {
+                let arity = ctor.arity();
+                Pat::Ctor(ctor, vec![Pat::Any; arity])
+            });
+
+        missing.insert(0, head);
+        Some(missing)
+    }
+}
@@ -0,0 +1,844 @@
+use lexpar::lexer::Span;
+
+use syntax::diagnostics::{Diagnostic, codes};
+use syntax::parser::ast::{
+    Ast, AstNode, BinOpKind, NodeId, Param, Pattern, Prototype, TypeExpr, UnOpKind,
+};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::compiler::pretty_span;
+
+pub use self::types::{Scheme, Type, TypeClass, TypeDecl, TypeVar};
+
+mod decls;
+mod matching;
+mod types;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.message, pretty_span(&self.span))
+    }
+}
+
+impl From<TypeError> for Diagnostic {
+    fn from(err: TypeError) -> Self {
+        Diagnostic::error(err.message)
+            .with_code(codes::TYPE_ERROR)
+            .with_label(err.span, "")
+    }
+}
+
+type InferResult = Result<Type, TypeError>;
+
+enum UnifyError {
+    Mismatch,
+    NotInClass(TypeClass, Type),
+}
+
+/// Inferred types of every expression and top-level function of a program.
+#[derive(Debug, Default)]
+pub struct TypeTable {
+    nodes: HashMap<NodeId, Type>,
+    defs: HashMap<String, Type>,
+    /// Types the variables of polymorphic names stand for at each of their uses.
+    instances: HashMap<NodeId, Vec<Type>>,
+    /// Polymorphic top-level functions. The types of their bodies keep the variables of the
+    /// scheme, each instance substitutes its own types for them.
+    schemes: HashMap<String, Scheme>,
+    decls: HashMap<String, TypeDecl>,
+    /// The union each constructor belongs to.
+    constructors: HashMap<String, String>,
+    /// Suspicious code that is still compiled, like a `match` missing some of the values.
+    warnings: Vec<Diagnostic>,
+}
+
+impl TypeTable {
+    pub fn node(&self, node: &AstNode) -> &Type {
+        self.nodes.get(&node.id).expect("Every expression must be typed.")
+    }
+
+    pub fn def(&self, name: &str) -> Option<&Type> {
+        self.defs.get(name)
+    }
+
+    /// Scheme of a polymorphic top-level function.
+    pub fn scheme(&self, name: &str) -> Option<&Scheme> {
+        self.schemes.get(name)
+    }
+
+    /// Declarations of the types of the program.
+    pub fn decls(&self) -> &HashMap<String, TypeDecl> {
+        &self.decls
+    }
+
+    pub fn record_fields(&self, name: &str) -> &[(String, Type)] {
+        match self.decls.get(name) {
+            Some(TypeDecl::Record(fields)) => fields,
+            _ => panic!("Type {} must be a record.", name),
+        }
+    }
+
+    /// The union, the tag and the field types of a constructor.
+    pub fn constructor(&self, name: &str) -> Option<(&str, usize, &[Type])> {
+        let union = self.constructors.get(name)?;
+
+        match &self.decls[union] {
+            TypeDecl::Union(cases) => cases
+                .iter()
+                .position(|(case, _)| case == name)
+                .map(|tag| (union.as_str(), tag, cases[tag].1.as_slice())),
+            TypeDecl::Record(_) => None,
+        }
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// Instance of the polymorphic name the reference or call refers to.
+    pub fn instance(&self, node: &AstNode) -> Option<&[Type]> {
+        self.instances.get(&node.id).map(Vec::as_slice)
+    }
+}
+
+/// Infers the types of a program given as its top-level block.
+///
+/// The builtins are visible to the whole program.
+pub fn check(ast: &AstNode, builtins: Vec<(&'static str, Scheme)>) -> Result<TypeTable, TypeError> {
+    let mut checker = TypeChecker::new();
+
+    for (name, scheme) in builtins {
+        // Polymorphic builtins have a function for each instance instead of a single one.
+        if scheme.vars.is_empty() {
+            checker.defs.insert(name.to_string(), scheme.ty.clone());
+        }
+
+        checker.scopes[0].insert(name.to_string(), scheme);
+    }
+
+    checker.check_top_level(ast)?;
+
+    Ok(checker.finish())
+}
+
+struct TypeChecker {
+    next_var: TypeVar,
+    /// Solutions of the type variables found so far.
+    substitution: HashMap<TypeVar, Type>,
+    /// Classes of the unsolved type variables that are constrained.
+    classes: HashMap<TypeVar, TypeClass>,
+    /// Chain of lexical scopes, innermost last.
+    /// The first one holds the builtins and the top-level definitions.
+    scopes: Vec<HashMap<String, Scheme>>,
+    nodes: HashMap<NodeId, Type>,
+    defs: HashMap<String, Type>,
+    instances: HashMap<NodeId, Vec<Type>>,
+    schemes: HashMap<String, Scheme>,
+    /// Variables of the schemes, they are left in the types of the polymorphic functions.
+    generic_vars: HashSet<TypeVar>,
+    /// Top-level functions whose definitions are not inferred yet.
+    pending: HashSet<String>,
+    /// Top-level functions used before their definitions were inferred. The uses fix their
+    /// types so they cannot be polymorphic.
+    forward: HashSet<String>,
+    decls: HashMap<String, TypeDecl>,
+    /// The record each field name refers to when the type of the record is not known,
+    /// the last one declaring it.
+    field_records: HashMap<String, String>,
+    constructors: HashMap<String, String>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl TypeChecker {
+    fn new() -> Self {
+        Self {
+            next_var: 0,
+            substitution: HashMap::new(),
+            classes: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            nodes: HashMap::new(),
+            defs: HashMap::new(),
+            instances: HashMap::new(),
+            schemes: HashMap::new(),
+            generic_vars: HashSet::new(),
+            pending: HashSet::new(),
+            forward: HashSet::new(),
+            decls: HashMap::new(),
+            field_records: HashMap::new(),
+            constructors: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn finish(self) -> TypeTable {
+        TypeTable {
+            nodes: self.nodes
+                .iter()
+                .map(|(id, ty)| (*id, self.finalize(ty)))
+                .collect(),
+            defs: self.defs
+                .iter()
+                .map(|(name, ty)| (name.clone(), self.finalize(ty)))
+                .collect(),
+            instances: self.instances
+                .iter()
+                .map(|(id, args)| (*id, args.iter().map(|ty| self.finalize(ty)).collect()))
+                .collect(),
+            schemes: self.schemes
+                .iter()
+                .map(|(name, scheme)| (name.clone(), Scheme {
+                    ty: self.finalize(&scheme.ty),
+                    ..scheme.clone()
+                }))
+                .collect(),
+            decls: self.decls,
+            constructors: self.constructors,
+            warnings: self.warnings,
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.next_var += 1;
+        Type::Var(self.next_var - 1)
+    }
+
+    fn is_top_level(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        assert!(!self.is_top_level(), "Cannot pop the top-level scope.");
+        self.scopes.pop();
+    }
+
+    fn bind(&mut self, name: String, ty: Type) {
+        self.scopes.last_mut().unwrap().insert(name, Scheme::mono(ty));
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Scheme> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn check_top_level(&mut self, ast: &AstNode) -> Result<(), TypeError> {
+        let items = match &*ast.expr {
+            Ast::Block(items) => items,
+            _ => return self.infer(ast).map(|_| ()),
+        };
+
+        self.declare_types(items)?;
+
+        // Declare all functions upfront so they can reference themselves or each other
+        // regardless of the definition order.
+        for item in items {
+            if let Ast::Function { prototype, .. } = &*item.expr {
+                // Only the builtins and the functions declared so far are bound at this point.
+                if self.lookup(&prototype.name).is_some() {
+                    return Err(TypeError {
+                        message: format!("Function {:?} is already defined", prototype.name),
+                        span: item.span.clone(),
+                    });
+                }
+
+                let ty = self.fresh();
+                self.defs.insert(prototype.name.clone(), ty.clone());
+                self.bind(prototype.name.clone(), ty);
+                self.pending.insert(prototype.name.clone());
+            }
+        }
+
+        for item in items {
+            match &*item.expr {
+                Ast::Function { prototype, .. } => {
+                    self.pending.remove(&prototype.name);
+                    self.infer(item)?;
+
+                    if prototype.name == "main" {
+                        self.check_main(&item.span, prototype)?;
+                    }
+
+                    self.generalize(&prototype.name);
+                },
+                _ => {
+                    self.infer(item)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The runtime calls `main` without arguments so its only parameter must be of type unit.
+    fn check_main(&mut self, span: &Span, prototype: &Prototype) -> Result<(), TypeError> {
+        let error = || TypeError {
+            message: "The main function must take a single parameter of type unit".to_string(),
+            span: span.clone(),
+        };
+
+        if prototype.args.len() != 1 {
+            return Err(error());
+        }
+
+        let (args, _) = self.apply(&self.defs["main"])
+            .split_function(1)
+            .expect("Functions must take all of their parameters.");
+
+        self.unify(&args[0], &Type::Unit).map_err(|_| error())
+    }
+
+    /// Makes a top-level function polymorphic in the type variables no other name refers to.
+    /// `main` is called by the runtime with no type to instantiate it with so it is left alone.
+    fn generalize(&mut self, name: &str) {
+        if name == "main" || self.forward.contains(name) {
+            return;
+        }
+
+        let mut bound = Vec::new();
+        for (other, scheme) in &self.scopes[0] {
+            if other != name {
+                self.free_vars(&scheme.ty, &scheme.vars, &mut bound);
+            }
+        }
+
+        let ty = self.apply(&self.defs[name]);
+        let mut vars = Vec::new();
+        self.free_vars(&ty, &bound, &mut vars);
+
+        if vars.is_empty() {
+            return;
+        }
+
+        let classes = vars
+            .iter()
+            .filter_map(|var| self.classes.get(var).map(|&class| (*var, class)))
+            .collect();
+
+        self.generic_vars.extend(vars.iter().cloned());
+
+        let scheme = Scheme { vars, classes, ty };
+        self.scopes[0].insert(name.to_string(), scheme.clone());
+        self.schemes.insert(name.to_string(), scheme);
+    }
+
+    /// Collects the unsolved variables of a type except for the `skipped` ones.
+    fn free_vars(&self, ty: &Type, skipped: &[TypeVar], vars: &mut Vec<TypeVar>) {
+        match ty {
+            Type::Var(var) if skipped.contains(var) => {},
+            Type::Var(var) => match self.substitution.get(var) {
+                Some(ty) => self.free_vars(ty, skipped, vars),
+                None => if !vars.contains(var) {
+                    vars.push(*var);
+                },
+            },
+            Type::Fn(arg, ret) => {
+                self.free_vars(arg, skipped, vars);
+                self.free_vars(ret, skipped, vars);
+            },
+            Type::Tuple(items) => for item in items {
+                self.free_vars(item, skipped, vars);
+            },
+            Type::List(item) => self.free_vars(item, skipped, vars),
+            _ => {},
+        }
+    }
+
+    fn infer(&mut self, ast: &AstNode) -> InferResult {
+        let ty = match &*ast.expr {
+            Ast::Integer(_) => Type::Int,
+            Ast::Float(_) => Type::Float,
+            Ast::Bool(_) => Type::Bool,
+            Ast::Char(_) => Type::Char,
+            Ast::Str(_) => Type::String,
+            Ast::Ref(name) => self.infer_ref(ast.id, &ast.span, name)?,
+            Ast::Tuple(items) => self.infer_tuple(items)?,
+            Ast::List(items) => self.infer_list(items)?,
+            Ast::Block(exprs) => self.infer_block(&ast.span, exprs)?,
+            Ast::Function { prototype, body } => self.infer_function(&ast.span, prototype, body)?,
+            Ast::Lambda { params, body } => self.infer_lambda(&ast.span, params, body)?,
+            Ast::Call { name, args } => self.infer_call(ast.id, &ast.span, name, args)?,
+            Ast::Apply { callee, args } => self.infer_apply(&ast.span, callee, args)?,
+            Ast::Variable { pattern, ty, expr } => {
+                self.infer_variable(&ast.span, pattern, ty.as_ref(), expr)?
+            },
+            Ast::Type { .. } => self.infer_type_decl(&ast.span)?,
+            Ast::Record { fields } => self.infer_record(&ast.span, fields)?,
+            Ast::Field { expr, field } => self.infer_field(&ast.span, expr, field)?,
+            Ast::Update { record, fields } => self.infer_update(&ast.span, record, fields)?,
+            Ast::BinOp { kind, lhs, rhs } => self.infer_binop(*kind, lhs, rhs)?,
+            Ast::Unary { kind, expr } => self.infer_unary(*kind, expr)?,
+            Ast::If { condition, then, el } => self.infer_if(condition, then, el)?,
+            Ast::Match { expr, arms } => self.infer_match(&ast.span, expr, arms)?,
+            Ast::For { var, start, end, body } => self.infer_for(var, start, end, body)?,
+            Ast::Error => unreachable!("Programs with syntax errors cannot be checked."),
+        };
+
+        self.nodes.insert(ast.id, ty.clone());
+
+        Ok(ty)
+    }
+
+    fn infer_scoped(&mut self, ast: &AstNode) -> InferResult {
+        self.push_scope();
+        let ty = self.infer(ast);
+        self.pop_scope();
+        ty
+    }
+
+    fn infer_ref(&mut self, id: NodeId, span: &Span, name: &String) -> InferResult {
+        if name == "_" {
+            return Err(TypeError {
+                message: "Illegal reference _".to_string(),
+                span: span.clone(),
+            });
+        }
+
+        let scheme = self.lookup(name)
+            .cloned()
+            .ok_or_else(|| TypeError {
+                message: format!("Unknown name {:?}", name),
+                span: span.clone(),
+            })?;
+
+        if scheme.vars.is_empty() {
+            let is_local = self.scopes[1..].iter().any(|scope| scope.contains_key(name));
+            if !is_local && self.pending.contains(name) {
+                self.forward.insert(name.clone());
+            }
+
+            return Ok(scheme.ty);
+        }
+
+        // Every use of a polymorphic name gets its own variables.
+        let args = scheme.vars.iter().map(|_| self.fresh()).collect::<Vec<_>>();
+        for (var, arg) in scheme.vars.iter().zip(&args) {
+            if let Some(&class) = scheme.classes.get(var) {
+                self.constrain(span, arg, class)?;
+            }
+        }
+
+        let ty = scheme.instantiate(&args);
+        self.instances.insert(id, args);
+
+        Ok(ty)
+    }
+
+    fn infer_tuple(&mut self, items: &Vec<AstNode>) -> InferResult {
+        let types = items
+            .iter()
+            .map(|item| self.infer(item))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if types.is_empty() {
+            Ok(Type::Unit)
+        } else {
+            Ok(Type::Tuple(types))
+        }
+    }
+
+    fn infer_list(&mut self, items: &Vec<AstNode>) -> InferResult {
+        let item = self.fresh();
+
+        for expr in items {
+            let ty = self.infer(expr)?;
+            self.expect(&expr.span, &item, &ty)?;
+        }
+
+        Ok(Type::List(Box::new(item)))
+    }
+
+    fn infer_block(&mut self, span: &Span, exprs: &Vec<AstNode>) -> InferResult {
+        self.push_scope();
+
+        let types = exprs
+            .iter()
+            .map(|expr| self.infer(expr))
+            .collect::<Result<Vec<_>, _>>();
+
+        self.pop_scope();
+
+        types?
+            .pop()
+            .ok_or_else(|| TypeError {
+                message: "Found empty block which is invalid value!".to_string(),
+                span: span.clone(),
+            })
+    }
+
+    fn infer_function(&mut self, span: &Span, prototype: &Prototype, body: &AstNode) -> InferResult {
+        let f = if self.is_top_level() {
+            self.defs[&prototype.name].clone()
+        } else {
+            let f = self.fresh();
+            self.bind(prototype.name.clone(), f.clone());
+            f
+        };
+
+        let ty = self.infer_abstraction(span, &prototype.args, prototype.ret.as_ref(), body)?;
+        self.expect(span, &f, &ty)?;
+
+        Ok(ty)
+    }
+
+    fn infer_lambda(&mut self, span: &Span, params: &Vec<Param>, body: &AstNode) -> InferResult {
+        if params.is_empty() {
+            return Err(TypeError {
+                message: "Anonymous functions must have at least one parameter".to_string(),
+                span: span.clone(),
+            });
+        }
+
+        self.infer_abstraction(span, params, None, body)
+    }
+
+    /// Infers the type of a function given its parameters and body.
+    /// Annotated parameters and return types are checked against the inferred ones.
+    fn infer_abstraction(
+        &mut self,
+        span: &Span,
+        params: &Vec<Param>,
+        ret: Option<&TypeExpr>,
+        body: &AstNode) -> InferResult
+    {
+        let args = params
+            .iter()
+            .map(|param| match &param.ty {
+                Some(ty) => self.resolve_type(span, ty),
+                None => Ok(self.fresh()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let ret_ty = match ret {
+            Some(ty) => Some(self.resolve_type(span, ty)?),
+            None => None,
+        };
+
+        self.push_scope();
+
+        for (param, ty) in params.iter().zip(&args) {
+            if param.name != "_" {
+                self.bind(param.name.clone(), ty.clone());
+            }
+        }
+
+        let ret = self.infer(body).and_then(|ty| match ret_ty {
+            Some(expected) => self.expect(&body.span, &expected, &ty).map(|_| expected),
+            None => Ok(ty),
+        });
+        self.pop_scope();
+
+        Ok(Type::function(args, ret?))
+    }
+
+    fn infer_call(&mut self, id: NodeId, span: &Span, name: &String, args: &Vec<AstNode>) -> InferResult {
+        let callee = self.infer_ref(id, span, name)?;
+        self.infer_application(span, &format!("{:?}", name), callee, args)
+    }
+
+    fn infer_apply(&mut self, span: &Span, callee: &AstNode, args: &Vec<AstNode>) -> InferResult {
+        let callee = self.infer(callee)?;
+        self.infer_application(span, "Expression", callee, args)
+    }
+
+    /// Applies the callee to the arguments one at a time like a curried function.
+    fn infer_application(
+        &mut self,
+        span: &Span,
+        description: &str,
+        callee: Type,
+        args: &Vec<AstNode>) -> InferResult
+    {
+        let mut ty = callee.clone();
+
+        for arg in args {
+            let arg_ty = self.infer(arg)?;
+
+            let (param, ret) = match self.resolve(&ty) {
+                Type::Fn(param, ret) => (*param, *ret),
+                var @ Type::Var(_) => {
+                    let param = self.fresh();
+                    let ret = self.fresh();
+                    let f = Type::Fn(Box::new(param.clone()), Box::new(ret.clone()));
+                    self.expect(span, &f, &var)?;
+                    (param, ret)
+                },
+                _ => return Err(TypeError {
+                    message: format!(
+                        "{} of type `{}` cannot be applied to {} argument(s)",
+                        description,
+                        self.apply(&callee),
+                        args.len()),
+                    span: span.clone(),
+                }),
+            };
+
+            self.expect(&arg.span, &param, &arg_ty)?;
+            ty = ret;
+        }
+
+        Ok(ty)
+    }
+
+    fn infer_variable(
+        &mut self,
+        span: &Span,
+        pattern: &Pattern,
+        annotation: Option<&TypeExpr>,
+        expr: &AstNode) -> InferResult
+    {
+        // `let` cannot fail so it only takes patterns matching any value.
+        if !self.is_irrefutable(pattern) {
+            return Err(TypeError {
+                message: "Patterns of `let` must match any value, use `match` instead".to_string(),
+                span: span.clone(),
+            });
+        }
+
+        self.check_bindings(span, pattern, &mut Vec::new())?;
+
+        let ty = self.infer(expr)?;
+        if let Some(annotation) = annotation {
+            let expected = self.resolve_type(span, annotation)?;
+            self.expect(&expr.span, &expected, &ty)?;
+        }
+        self.bind_pattern(span, pattern, &ty)?;
+
+        Ok(Type::Unit)
+    }
+
+    fn infer_binop(&mut self, kind: BinOpKind, lhs: &AstNode, rhs: &AstNode) -> InferResult {
+        use BinOpKind::*;
+
+        let lhs_ty = self.infer(lhs)?;
+        let rhs_ty = self.infer(rhs)?;
+
+        if kind == And || kind == Or {
+            self.expect(&lhs.span, &Type::Bool, &lhs_ty)?;
+            self.expect(&rhs.span, &Type::Bool, &rhs_ty)?;
+            return Ok(Type::Bool);
+        }
+
+        if kind == Cons {
+            self.expect(&rhs.span, &Type::List(Box::new(lhs_ty)), &rhs_ty)?;
+            return Ok(rhs_ty);
+        }
+
+        if kind == Append {
+            let list = Type::List(Box::new(self.fresh()));
+            self.expect(&lhs.span, &list, &lhs_ty)?;
+            self.expect(&rhs.span, &list, &rhs_ty)?;
+            return Ok(list);
+        }
+
+        // There are no implicit conversions so both operands must be of the same type.
+        self.expect(&rhs.span, &lhs_ty, &rhs_ty)?;
+
+        match kind {
+            Add => {
+                self.constrain(&lhs.span, &lhs_ty, TypeClass::Addable)?;
+                Ok(lhs_ty)
+            },
+            Sub | Mul | Div | Mod => {
+                self.constrain(&lhs.span, &lhs_ty, TypeClass::Numeric)?;
+                Ok(lhs_ty)
+            },
+            LessThan | LessEq | GreaterThan | GreaterEq => {
+                self.constrain(&lhs.span, &lhs_ty, TypeClass::Ordered)?;
+                Ok(Type::Bool)
+            },
+            Eq | NotEq => {
+                self.constrain(&lhs.span, &lhs_ty, TypeClass::Equatable)?;
+                Ok(Type::Bool)
+            },
+            And | Or | Cons | Append => unreachable!(),
+        }
+    }
+
+    fn infer_unary(&mut self, kind: UnOpKind, expr: &AstNode) -> InferResult {
+        let ty = self.infer(expr)?;
+
+        match kind {
+            UnOpKind::Neg => self.constrain(&expr.span, &ty, TypeClass::Numeric)?,
+            UnOpKind::Not => self.expect(&expr.span, &Type::Bool, &ty)?,
+        }
+
+        Ok(ty)
+    }
+
+    fn infer_if(&mut self, cond: &AstNode, then: &AstNode, el: &Option<AstNode>) -> InferResult {
+        let cond_ty = self.infer(cond)?;
+        self.expect(&cond.span, &Type::Bool, &cond_ty)?;
+
+        let then_ty = self.infer_scoped(then)?;
+
+        if let Some(el) = el {
+            let el_ty = self.infer_scoped(el)?;
+            self.expect(&el.span, &then_ty, &el_ty)?;
+            Ok(then_ty)
+        } else {
+            // Without an `else` there is no value to produce when the condition is false.
+            self.expect(&then.span, &Type::Unit, &then_ty)?;
+            Ok(Type::Unit)
+        }
+    }
+
+    fn infer_for(
+        &mut self,
+        var: &String,
+        start: &AstNode,
+        end: &AstNode,
+        body: &AstNode) -> InferResult
+    {
+        let start_ty = self.infer(start)?;
+        self.expect(&start.span, &Type::Int, &start_ty)?;
+
+        let end_ty = self.infer(end)?;
+        self.expect(&end.span, &Type::Int, &end_ty)?;
+
+        self.push_scope();
+        if var != "_" {
+            self.bind(var.clone(), Type::Int);
+        }
+        let body_ty = self.infer(body);
+        self.pop_scope();
+        body_ty?;
+
+        Ok(Type::Unit)
+    }
+
+    fn expect(&mut self, span: &Span, expected: &Type, actual: &Type) -> Result<(), TypeError> {
+        self.unify(expected, actual).map_err(|err| TypeError {
+            message: match err {
+                UnifyError::Mismatch => format!(
+                    "Expected type `{}` but found `{}`",
+                    self.apply(expected),
+                    self.apply(actual)),
+                UnifyError::NotInClass(class, ty) => format!(
+                    "Expected {} but found `{}`",
+                    class.describe(),
+                    self.apply(&ty)),
+            },
+            span: span.clone(),
+        })
+    }
+
+    /// Requires the type to be in the class.
+    fn constrain(&mut self, span: &Span, ty: &Type, class: TypeClass) -> Result<(), TypeError> {
+        self.add_class(ty, class).map_err(|err| TypeError {
+            message: match err {
+                UnifyError::NotInClass(class, ty) => format!(
+                    "Expected {} but found `{}`",
+                    class.describe(),
+                    self.apply(&ty)),
+                UnifyError::Mismatch => unreachable!(),
+            },
+            span: span.clone(),
+        })
+    }
+
+    fn add_class(&mut self, ty: &Type, class: TypeClass) -> Result<(), UnifyError> {
+        match self.resolve(ty) {
+            Type::Var(var) => {
+                let class = self.classes.get(&var).map_or(class, |&other| class.min(other));
+                self.classes.insert(var, class);
+                Ok(())
+            },
+            ty => if class.admits(&ty) {
+                Ok(())
+            } else {
+                Err(UnifyError::NotInClass(class, ty))
+            },
+        }
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), UnifyError> {
+        match (self.resolve(a), self.resolve(b)) {
+            (Type::Var(a), Type::Var(b)) if a == b => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return Err(UnifyError::Mismatch);
+                }
+
+                // The solution inherits the constraints of the variable.
+                if let Some(class) = self.classes.remove(&var) {
+                    self.add_class(&ty, class)?;
+                }
+
+                self.substitution.insert(var, ty);
+                Ok(())
+            },
+            (Type::Fn(a_arg, a_ret), Type::Fn(b_arg, b_ret)) => {
+                self.unify(&a_arg, &b_arg)?;
+                self.unify(&a_ret, &b_ret)
+            },
+            (Type::List(a), Type::List(b)) => self.unify(&a, &b),
+            (Type::Tuple(a), Type::Tuple(b)) => {
+                if a.len() != b.len() {
+                    return Err(UnifyError::Mismatch);
+                }
+
+                a.iter().zip(&b).map(|(a, b)| self.unify(a, b)).collect()
+            },
+            (a, b) => if a == b { Ok(()) } else { Err(UnifyError::Mismatch) },
+        }
+    }
+
+    fn occurs(&self, var: TypeVar, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(other) => var == other,
+            Type::Fn(arg, ret) => self.occurs(var, &arg) || self.occurs(var, &ret),
+            Type::Tuple(items) => items.iter().any(|item| self.occurs(var, item)),
+            Type::List(item) => self.occurs(var, &item),
+            _ => false,
+        }
+    }
+
+    /// Follows the substitution until the type is no longer a solved variable.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match self.substitution.get(var) {
+                Some(ty) => self.resolve(ty),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    /// Applies the substitution to the whole type.
+    fn apply(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::Fn(arg, ret) => Type::Fn(Box::new(self.apply(&arg)), Box::new(self.apply(&ret))),
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|item| self.apply(item)).collect()),
+            Type::List(item) => Type::List(Box::new(self.apply(&item))),
+            ty => ty,
+        }
+    }
+
+    /// Applies the substitution and defaults the variables that are left.
+    /// Constrained ones get the default of their class, the rest are not constrained by anything
+    /// so any type is as good as another and they become unit.
+    fn finalize(&self, ty: &Type) -> Type {
+        match self.apply(ty) {
+            // Each instance of a polymorphic function substitutes its own types.
+            Type::Var(var) if self.generic_vars.contains(&var) => Type::Var(var),
+            Type::Var(var) => self.classes.get(&var).map_or(Type::Unit, |class| class.default_type()),
+            Type::Fn(arg, ret) => Type::Fn(Box::new(self.finalize(&arg)), Box::new(self.finalize(&ret))),
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|item| self.finalize(item)).collect()),
+            Type::List(item) => Type::List(Box::new(self.finalize(&item))),
+            ty => ty,
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use lexpar::lexer::Span;
+
+use syntax::diagnostics::codes;
+use syntax::lexer;
+use syntax::parser::Parser;
+use syntax::parser::ast::{Ast, AstNode};
+
+use crate::compiler;
+
+use super::{TypeError, TypeTable, check};
+use super::types::TypeClass;
+
+fn check_source(source: &str) -> Result<TypeTable, TypeError> {
+    let (items, errors) = Parser::parse(lexer::lexer().src_iter(source));
+    assert!(errors.is_empty(), "Syntax errors {:?}", errors);
+
+    let mut ast = AstNode::new(Span::new(0, source.len(), 0), Ast::Block(items));
+    ast.number_nodes();
+
+    check(&ast, compiler::builtins())
+}
+
+fn checked(source: &str) -> TypeTable {
+    check_source(source).unwrap_or_else(|err| panic!("Unexpected type error: {}", err))
+}
+
+fn error(source: &str) -> TypeError {
+    match check_source(source) {
+        Ok(_) => panic!("Expected a type error"),
+        Err(err) => err,
+    }
+}
+
+/// Type of a top-level function as it is printed.
+fn type_of(source: &str, name: &str) -> String {
+    checked(source)
+        .def(name)
+        .unwrap_or_else(|| panic!("{} is not defined", name))
+        .to_string()
+}
+
+#[test]
+fn infers_functions() {
+    assert_eq!(type_of("let f x = x + 1\n", "f"), "int -> int");
+    assert_eq!(type_of("let f x = x * 2.0\n", "f"), "float -> float");
+    assert_eq!(type_of("let f x = if x then 1 else 2\n", "f"), "bool -> int");
+    assert_eq!(type_of("let f a b = a < b and b > 0\n", "f"), "int -> int -> bool");
+    assert_eq!(type_of("let f s = s + \"!\"\n", "f"), "string -> string");
+    assert_eq!(type_of("let f c = int_of_char c\n", "f"), "char -> int");
+}
+
+#[test]
+fn infers_through_calls() {
+    let source = "\
+let half x = x / 2.0
+let f y = half (float_of_int y)
+";
+
+    assert_eq!(type_of(source, "half"), "float -> float");
+    assert_eq!(type_of(source, "f"), "int -> float");
+}
+
+#[test]
+fn infers_recursive_functions() {
+    let source = "let fact n = if n < 2 then 1 else n * fact (n - 1)\n";
+    assert_eq!(type_of(source, "fact"), "int -> int");
+}
+
+#[test]
+fn infers_mutually_recursive_functions() {
+    let source = "\
+let even n = if n == 0 then true else odd (n - 1)
+let odd n = if n == 0 then false else even (n - 1)
+";
+
+    assert_eq!(type_of(source, "even"), "int -> bool");
+    assert_eq!(type_of(source, "odd"), "int -> bool");
+}
+
+#[test]
+fn infers_let_bindings() {
+    let source = "\
+let limit = 10
+let clamp x = if x > limit then limit else x
+let twice x =
+    let y = x * 2.0
+    y + 1.0
+";
+
+    assert_eq!(type_of(source, "clamp"), "int -> int");
+    assert_eq!(type_of(source, "twice"), "float -> float");
+}
+
+#[test]
+fn no_implicit_conversions() {
+    let source = "let f x = x + 1.0 + 1\n";
+    let err = error(source);
+
+    assert_eq!(err.message, "Expected type `float` but found `int`");
+    assert_eq!(err.span.lo, source.rfind('1').unwrap());
+}
+
+#[test]
+fn operators_need_the_right_types() {
+    assert_eq!(
+        error("let f x = true + x\n").message,
+        "Expected a numeric type or string but found `bool`");
+    assert_eq!(
+        error("let f x = \"a\" * x\n").message,
+        "Expected a numeric type but found `string`");
+    assert_eq!(
+        error("let f x = 1 and x\n").message,
+        "Expected type `bool` but found `int`");
+}
+
+#[test]
+fn conditions_must_be_bool() {
+    assert_eq!(
+        error("let f x = if 1 then x else x\n").message,
+        "Expected type `bool` but found `int`");
+}
+
+#[test]
+fn branches_must_agree() {
+    assert_eq!(
+        error("let f x = if x then 1 else 2.0\n").message,
+        "Expected type `int` but found `float`");
+}
+
+#[test]
+fn if_without_else_must_be_unit() {
+    assert_eq!(
+        error("let f x = if x then 1\n").message,
+        "Expected type `unit` but found `int`");
+
+    assert_eq!(type_of("let f x = if x then print_int 1\n", "f"), "bool -> unit");
+}
+
+#[test]
+fn calls_must_match_the_parameters() {
+    assert_eq!(
+        error("let f x = x + 1\nlet g _ = f true\n").message,
+        "Expected type `int` but found `bool`");
+    assert_eq!(
+        error("let g _ = print_int 1 2\n").message,
+        "\"print_int\" of type `int -> unit` cannot be applied to 2 argument(s)");
+}
+
+#[test]
+fn unknown_names() {
+    assert_eq!(error("let f x = y\n").message, "Unknown name \"y\"");
+}
+
+#[test]
+fn let_bindings_are_scoped() {
+    let source = "\
+let x = 1
+let f y =
+    let x = y + 0.5
+    x
+
+let g y = x + y
+let h b =
+    let x = 1
+    if b then x else
+        let x = 2.0
+        int_of_float x
+";
+
+    assert_eq!(type_of(source, "f"), "float -> float");
+    assert_eq!(type_of(source, "g"), "int -> int");
+    assert_eq!(type_of(source, "h"), "bool -> int");
+
+    assert_eq!(
+        error("let f _ =\n    let y = 1\n    y\n\nlet g _ = y\n").message,
+        "Unknown name \"y\"");
+}
+
+#[test]
+fn names_are_used_after_their_definition() {
+    let source = "let f _ =\n    let a = b\n    let b = 1\n    a\n";
+    let err = error(source);
+
+    assert_eq!(err.message, "Unknown name \"b\"");
+    assert_eq!(err.span.lo, source.find('b').unwrap());
+
+    assert_eq!(
+        error("let f _ = limit\nlet limit = 1\n").message,
+        "Unknown name \"limit\"");
+}
+
+#[test]
+fn functions_are_defined_once() {
+    assert_eq!(
+        error("let f x = x\nlet f y = y\n").message,
+        "Function \"f\" is already defined");
+    assert_eq!(
+        error("let print_int x = x\n").message,
+        "Function \"print_int\" is already defined");
+}
+
+#[test]
+fn main_takes_unit() {
+    assert_eq!(type_of("let main _ = print_int 1\n", "main"), "unit -> unit");
+    assert_eq!(type_of("let main args = print_int 1\n", "main"), "unit -> unit");
+
+    let message = "The main function must take a single parameter of type unit";
+    assert_eq!(error("let main x = print_int x\n").message, message);
+    assert_eq!(error("let main a b = print_int 1\n").message, message);
+}
+
+#[test]
+fn functions_are_values() {
+    assert_eq!(type_of("let apply_inc f = f 1 + 1\n", "apply_inc"), "(int -> int) -> int");
+
+    let source = "\
+let add a b = a * b + 1
+let partial u = if u then add 1 else add 2
+";
+    assert_eq!(type_of(source, "partial"), "bool -> int -> int");
+}
+
+#[test]
+fn applying_too_many_arguments() {
+    assert_eq!(
+        error("let f x = x + 1\nlet g _ = f 1 2\n").message,
+        "\"f\" of type `int -> int` cannot be applied to 2 argument(s)");
+    assert_eq!(
+        error("let f x = x + 1\nlet g _ = (f 1) 2\n").message,
+        "Expression of type `int` cannot be applied to 1 argument(s)");
+}
+
+#[test]
+fn infers_tuples() {
+    assert_eq!(type_of("let pair x = (x + 1, \"one\")\n", "pair"), "int -> int * string");
+
+    let source = "\
+let sum p =
+    let (a, b) = p
+    a + b + 0
+";
+    assert_eq!(type_of(source, "sum"), "int * int -> int");
+}
+
+#[test]
+fn destructuring_needs_the_same_length() {
+    let source = "\
+let f _ =
+    let (a, b) = (1, 2, 3)
+    a
+";
+    let message = error(source).message;
+    assert!(message.ends_with("but found `int * int * int`"), "{}", message);
+}
+
+const POINT: &str = "type Point = { x: float; y: float }\n";
+
+#[test]
+fn infers_records() {
+    let source = format!("{}{}", POINT, "\
+let norm p = p.x * p.x + p.y * p.y
+let make x = { x = x; y = 0.0 }
+let move p = { p with x = 1.0 }
+");
+
+    assert_eq!(type_of(&source, "norm"), "Point -> float");
+    assert_eq!(type_of(&source, "make"), "float -> Point");
+    assert_eq!(type_of(&source, "move"), "Point -> Point");
+}
+
+#[test]
+fn records_need_every_field_once() {
+    let error = |source: &str| error(&format!("{}{}", POINT, source)).message;
+
+    assert_eq!(error("let f x = { x = x }\n"), "Field \"y\" of record `Point` is not set");
+    assert_eq!(error("let f x = { x = x; y = x; x = x }\n"), "Field \"x\" is set more than once");
+    assert_eq!(error("let f x = { x = 1; y = 2.0 }\n"), "Expected type `float` but found `int`");
+}
+
+#[test]
+fn unknown_fields() {
+    let error = |source: &str| error(&format!("{}{}", POINT, source)).message;
+
+    assert_eq!(error("let f _ = { z = 1 }\n"), "No record has a field \"z\"");
+    assert_eq!(error("let f (p: Point) = p.z\n"), "Type `Point` has no field \"z\"");
+    assert_eq!(error("let f x = (x + 1).x\n"), "Type `int` has no field \"x\"");
+}
+
+#[test]
+fn record_declarations() {
+    assert_eq!(
+        error("type P = { x: int; x: int }\n").message,
+        "Field \"x\" is declared more than once");
+    assert_eq!(error("type P = { x: Q }\n").message, "Unknown type `Q`");
+}
+
+const SHAPE: &str = "type Shape = | Circle of float | Rect of float * float | Empty\n";
+
+#[test]
+fn constructors_are_functions() {
+    let source = format!("{}{}", SHAPE, "let circle r = Circle (r * 2.0)\n");
+
+    assert_eq!(type_of(&source, "Rect"), "float -> float -> Shape");
+    assert_eq!(type_of(&source, "circle"), "float -> Shape");
+    assert_eq!(
+        error(&format!("{}{}", SHAPE, "let f _ = Circle 1\n")).message,
+        "Expected type `float` but found `int`");
+}
+
+#[test]
+fn constructors_are_defined_once() {
+    assert_eq!(
+        error("type S = | A | B | A\n").message,
+        "Constructor \"A\" is declared more than once");
+    assert_eq!(
+        error(&format!("{}{}", SHAPE, "type Other = | Empty\n")).message,
+        "Constructor \"Empty\" is already defined");
+    assert_eq!(
+        error(&format!("{}{}", SHAPE, "type Shape = | Square of float\n")).message,
+        "Type `Shape` is already defined");
+}
+
+/// Codes and messages of the warnings of a program without type errors.
+fn warnings(source: &str) -> Vec<(&'static str, String)> {
+    checked(source)
+        .warnings()
+        .iter()
+        .map(|warning| (warning.code.unwrap(), warning.message.clone()))
+        .collect()
+}
+
+#[test]
+fn infers_matches() {
+    let source = "\
+let f xs =
+    match xs with
+    | [] -> 0
+    | [x] -> x + 1
+    | _ :: _ -> 2
+";
+    assert_eq!(type_of(source, "f"), "int list -> int");
+
+    let source = "\
+let f p =
+    match p with
+    | (0, s) -> s
+    | (_, s) -> s + \"!\"
+";
+    assert_eq!(type_of(source, "f"), "int * string -> string");
+}
+
+#[test]
+fn arms_must_agree() {
+    let source = "\
+let f x =
+    match x with
+    | 0 -> 1
+    | _ -> \"one\"
+";
+    assert_eq!(error(source).message, "Expected type `int` but found `string`");
+
+    let source = "\
+let f x =
+    match x + 1 with
+    | \"one\" -> 1
+    | _ -> 0
+";
+    assert_eq!(error(source).message, "Expected type `int` but found `string`");
+}
+
+#[test]
+fn warns_about_unreachable_arms() {
+    let source = "\
+let f x =
+    match x with
+    | _ -> 0
+    | 1 -> 1
+";
+    assert_eq!(warnings(source), [(codes::UNREACHABLE_ARM, "This arm is never matched".to_string())]);
+
+    let source = "\
+let f x =
+    match x with
+    | true -> 0
+    | false -> 1
+    | _ -> 2
+";
+    assert_eq!(warnings(source), [(codes::UNREACHABLE_ARM, "This arm is never matched".to_string())]);
+}
+
+#[test]
+fn warns_about_incomplete_matches() {
+    let incomplete = |example: &str| {
+        vec![(codes::INCOMPLETE_MATCH, format!("Incomplete match, `{}` is not matched", example))]
+    };
+
+    assert_eq!(warnings("let f x = match x with | true -> 0\n"), incomplete("false"));
+    assert_eq!(warnings("let f x = match x with | [] -> 0\n"), incomplete("_ :: _"));
+
+    let source = format!("{}{}", SHAPE, "\
+let f s =
+    match s with
+    | Circle _ -> 0
+    | Rect _ _ -> 1
+");
+    assert_eq!(warnings(&source), incomplete("Empty"));
+}
+
+#[test]
+fn complete_matches_have_no_warnings() {
+    let source = format!("{}{}", SHAPE, "\
+let f s =
+    match s with
+    | Circle _ -> 0
+    | Rect _ _ -> 1
+    | Empty -> 2
+let g x =
+    match x with
+    | [] -> 0
+    | h :: _ -> h
+");
+    assert!(warnings(&source).is_empty());
+}
+
+#[test]
+fn annotations_decide_types() {
+    assert_eq!(type_of("let f (x: float) = x\n", "f"), "float -> float");
+    assert_eq!(type_of("let f x: string = x\n", "f"), "string -> string");
+    assert_eq!(type_of("let f (g: int -> bool) x = g x\n", "f"), "(int -> bool) -> int -> bool");
+    assert_eq!(type_of("let f (xs: int list) = xs\n", "f"), "int list -> int list");
+}
+
+#[test]
+fn annotations_must_match() {
+    assert_eq!(error("let f (x: int) = x + 1.0\n").message, "Expected type `int` but found `float`");
+    assert_eq!(error("let f x: bool = x + 1\n").message, "Expected type `bool` but found `int`");
+    assert_eq!(
+        error("let f _ =\n    let x: string = 1\n    x\n").message,
+        "Expected type `string` but found `int`");
+    assert_eq!(error("let f (x: number) = x\n").message, "Unknown type `number`");
+}
+
+#[test]
+fn generalizes_top_level_functions() {
+    let source = "\
+let id x = x
+let add a b = a + b
+let both (u: unit) = (id 1, id \"one\", add 1.0 2.0, add \"a\" \"b\")
+";
+    let table = checked(source);
+
+    let id = table.scheme("id").expect("id is not polymorphic");
+    assert_eq!(id.vars.len(), 1);
+    assert!(id.classes.is_empty());
+
+    let add = table.scheme("add").expect("add is not polymorphic");
+    assert_eq!(add.vars.len(), 1);
+    assert_eq!(add.classes.values().collect::<Vec<_>>(), [&TypeClass::Addable]);
+
+    assert_eq!(table.def("both").unwrap().to_string(), "unit -> int * string * float * string");
+}
+
+#[test]
+fn instances_keep_the_class_constraints() {
+    assert_eq!(
+        error("let add a b = a + b\nlet f _ = add true false\n").message,
+        "Expected a numeric type or string but found `bool`");
+}
+
+#[test]
+fn some_functions_have_a_single_type() {
+    let table = checked("let main _ = ()\nlet f x = g x\nlet g x = x\n");
+    assert!(table.scheme("main").is_none());
+    assert!(table.scheme("g").is_none());
+
+    assert_eq!(
+        error("let f _ = (g 1, g \"one\")\nlet g x = x\n").message,
+        "Expected type `int` but found `string`");
+
+    let source = "\
+let f _ =
+    let id x = x
+    (id 1, id \"one\")
+";
+    assert_eq!(error(source).message, "Expected type `int` but found `string`");
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fmt;
+
+pub type TypeVar = u32;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Type {
+    /// Not yet inferred type.
+    Var(TypeVar),
+    Unit,
+    Bool,
+    Int,
+    Float,
+    /// Unicode scalar value.
+    Char,
+    String,
+    /// Tuple of at least two elements, the empty tuple is unit.
+    Tuple(Vec<Type>),
+    List(Box<Type>),
+    /// Type declared by the program.
+    Named(String),
+    /// Curried function type `arg -> ret`.
+    Fn(Box<Type>, Box<Type>),
+}
+
+impl Type {
+    pub fn function(args: Vec<Type>, ret: Type) -> Type {
+        args.into_iter()
+            .rev()
+            .fold(ret, |ret, arg| Type::Fn(Box::new(arg), Box::new(ret)))
+    }
+
+    /// Splits off the first `arity` argument types of a curried function type.
+    ///
+    /// Returns `None` if the type takes less arguments.
+    pub fn split_function(&self, arity: usize) -> Option<(Vec<Type>, Type)> {
+        let mut args = Vec::with_capacity(arity);
+        let mut ty = self;
+
+        while args.len() < arity {
+            match ty {
+                Type::Fn(arg, ret) => {
+                    args.push((**arg).clone());
+                    ty = ret;
+                },
+                _ => return None,
+            }
+        }
+
+        Some((args, ty.clone()))
+    }
+
+    /// Replaces the variables found in `map`.
+    pub fn substitute(&self, map: &HashMap<TypeVar, Type>) -> Type {
+        match self {
+            Type::Var(var) => map.get(var).cloned().unwrap_or_else(|| self.clone()),
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|item| item.substitute(map)).collect()),
+            Type::List(item) => Type::List(Box::new(item.substitute(map))),
+            Type::Fn(arg, ret) => Type::Fn(Box::new(arg.substitute(map)), Box::new(ret.substitute(map))),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Definition of a type declared by the program.
+#[derive(Clone, Debug)]
+pub enum TypeDecl {
+    /// Fields in the order of the declaration.
+    Record(Vec<(String, Type)>),
+    /// Constructors with the types of their fields, the index of a constructor is its tag.
+    Union(Vec<(String, Vec<Type>)>),
+}
+
+/// Type of a polymorphic name, `vars` stand for any types at each use.
+#[derive(Clone, Debug)]
+pub struct Scheme {
+    pub vars: Vec<TypeVar>,
+    /// Classes the types standing for some of the variables must belong to.
+    pub classes: HashMap<TypeVar, TypeClass>,
+    pub ty: Type,
+}
+
+impl Scheme {
+    /// Type of a name that is not polymorphic.
+    pub fn mono(ty: Type) -> Self {
+        Self {
+            vars: Vec::new(),
+            classes: HashMap::new(),
+            ty,
+        }
+    }
+
+    /// The type of a use of the name where the variables are `args` in order.
+    pub fn instantiate(&self, args: &[Type]) -> Type {
+        let map = self.vars.iter().cloned().zip(args.iter().cloned()).collect();
+        self.ty.substitute(&map)
+    }
+}
+
+/// Constraint on the types a type variable can be solved with.
+///
+/// Every class admits a subset of the types of the next one so the stricter of two classes is
+/// simply the smaller one.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum TypeClass {
+    /// Supports arithmetic.
+    Numeric,
+    /// Supports `+`, arithmetic or concatenation.
+    Addable,
+    /// Supports ordering comparisons.
+    Ordered,
+    /// Supports equality comparisons.
+    Equatable,
+}
+
+impl TypeClass {
+    /// Whether a concrete type belongs to the class.
+    pub fn admits(self, ty: &Type) -> bool {
+        match ty {
+            Type::Int | Type::Float => true,
+            Type::String => self >= TypeClass::Addable,
+            Type::Char => self >= TypeClass::Ordered,
+            Type::Bool | Type::Unit => self >= TypeClass::Equatable,
+            _ => false,
+        }
+    }
+
+    /// The type a variable of this class gets when nothing else determines it.
+    pub fn default_type(self) -> Type {
+        Type::Int
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            TypeClass::Numeric => "a numeric type",
+            TypeClass::Addable => "a numeric type or string",
+            TypeClass::Ordered => "an ordered type",
+            TypeClass::Equatable => "a type with equality",
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Var(var) => {
+                let letter = (b'a' + (var % 26) as u8) as char;
+                match var / 26 {
+                    0 => write!(f, "'{}", letter),
+                    n => write!(f, "'{}{}", letter, n),
+                }
+            },
+            Type::Unit => write!(f, "unit"),
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Char => write!(f, "char"),
+            Type::String => write!(f, "string"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Tuple(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " * ")?;
+                    }
+
+                    match item {
+                        Type::Tuple(_) | Type::Fn(..) => write!(f, "({})", item)?,
+                        _ => write!(f, "{}", item)?,
+                    }
+                }
+                Ok(())
+            },
+            Type::List(item) => match **item {
+                Type::Tuple(_) | Type::Fn(..) => write!(f, "({}) list", item),
+                _ => write!(f, "{} list", item),
+            },
+            Type::Fn(arg, ret) => match **arg {
+                Type::Fn(..) => write!(f, "({}) -> {}", arg, ret),
+                _ => write!(f, "{} -> {}", arg, ret),
+            },
+        }
+    }
+}
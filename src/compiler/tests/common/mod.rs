@@ -0,0 +1,86 @@
+//! Runs the compiler binary on programs written to temporary directories.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A `main.dg` in a directory of its own, removed when dropped.
+pub struct Program {
+    pub dir: PathBuf,
+}
+
+impl Program {
+    pub fn new(source: &str) -> Self {
+        let dir = env::temp_dir().join(format!(
+            "degu-test-{}-{}",
+            process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::SeqCst)));
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.dg"), source).unwrap();
+
+        Self { dir }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join("main.dg")
+    }
+
+    /// Runs the compiler in the directory of the program.
+    pub fn compiler(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_compiler"))
+            .current_dir(&self.dir)
+            .args(args)
+            .output()
+            .expect("Cannot run the compiler.")
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Runs the program with the JIT and returns what it printed.
+pub fn run(source: &str) -> String {
+    let output = Program::new(source).compiler(&["run", "-q", "main.dg"]);
+
+    assert!(
+        output.status.success(),
+        "The program failed with:\n{}",
+        String::from_utf8_lossy(&output.stderr));
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs the program with the JIT expecting it to fail to compile or to exit with an error.
+/// Returns the errors it reported.
+pub fn fail(source: &str) -> String {
+    let output = Program::new(source).compiler(&["run", "main.dg"]);
+
+    assert!(
+        !output.status.success(),
+        "The program did not fail, it printed:\n{}",
+        String::from_utf8_lossy(&output.stdout));
+
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Runs the executable built from the program and returns what it printed.
+pub fn run_executable(program: &Program) -> String {
+    let result = program.compiler(&["build", "-q", "main.dg"]);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    let executable = program.dir.join("main").with_extension(env::consts::EXE_EXTENSION);
+    let output = Command::new(executable).output().unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout).unwrap()
+}
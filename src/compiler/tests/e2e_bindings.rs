@@ -0,0 +1,70 @@
+mod common;
+
+#[test]
+pub fn e2e_top_level_bindings() {
+    let output = common::run("\
+let limit = 10
+let scale = 2.5
+let clamp x = if x > limit then limit else x
+
+let main _ =
+    print_int (clamp 25)
+    print_int (clamp 7)
+    print_number (scale * 2.0)
+");
+
+    assert_eq!(output, "10\n7\n5\n");
+}
+
+#[test]
+pub fn e2e_local_bindings() {
+    let output = common::run("\
+let area w h =
+    let width = w + 1
+    let height = h * 2
+    width * height
+
+let main _ =
+    let a = area 2 3
+    let b = a - 8
+    print_int a
+    print_int b
+");
+
+    assert_eq!(output, "18\n10\n");
+}
+
+#[test]
+pub fn e2e_shadowing_in_nested_scopes() {
+    let output = common::run("\
+let x = 1
+let show_x _ = print_int x
+
+let inner x =
+    let x = x * 10
+    x + 1
+
+let main _ =
+    show_x ()
+    let x = 2
+    print_int (inner x)
+    print_int x
+    for i in 1..2 do
+        let x = x + i
+        print_int x
+");
+
+    assert_eq!(output, "1\n21\n2\n3\n4\n");
+}
+
+#[test]
+pub fn e2e_use_before_definition() {
+    let errors = common::fail("\
+let main _ =
+    print_int y
+    let y = 1
+");
+
+    assert!(errors.contains("error[E0100]: Unknown name \"y\""), "{}", errors);
+    assert!(errors.contains("main.dg:2:15"), "{}", errors);
+}
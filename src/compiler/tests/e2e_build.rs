@@ -0,0 +1,89 @@
+mod common;
+
+use std::fs;
+
+use common::{Program, run_executable};
+
+#[test]
+pub fn e2e_refuses_to_overwrite_the_input() {
+    let source = "let main _ = print_int 1\n";
+    let program = Program::new(source);
+
+    for output in &["main.dg", "./main.dg"] {
+        let result = program.compiler(&["build", "--emit=ast", "-o", output, "main.dg"]);
+
+        assert!(!result.status.success());
+        assert!(String::from_utf8_lossy(&result.stderr).contains("Refusing to overwrite"));
+        assert_eq!(fs::read_to_string(program.path()).unwrap(), source);
+    }
+}
+
+// Executables have no extension on Unix so they would replace an input without one.
+#[cfg(unix)]
+#[test]
+pub fn e2e_refuses_to_overwrite_an_input_without_extension() {
+    let source = "let main _ = print_int 1\n";
+    let program = Program::new(source);
+    fs::write(program.dir.join("main"), source).unwrap();
+
+    let result = program.compiler(&["build", "--emit=ir,exe", "main"]);
+
+    assert!(!result.status.success());
+    assert!(!program.dir.join("main.ll").exists());
+    assert_eq!(fs::read_to_string(program.dir.join("main")).unwrap(), source);
+}
+
+#[test]
+pub fn e2e_executables_print_numbers_like_the_jit() {
+    let source = "\
+let main _ =
+    print_number 1000000000000000000000.0
+    print_number 0.1
+    print_number 1.5
+    print_number 100.0
+    print_number (0.1 + 0.2)
+    print_number (1.0 / 3.0)
+    print_number 0.0000001
+    print_number (-2.5)
+    print_number (0.0 / 0.0)
+    print_number (1.0 / 0.0)
+    print_number (-1.0 / 0.0)
+";
+    let expected = "\
+1000000000000000000000
+0.1
+1.5
+100
+0.30000000000000004
+0.3333333333333333
+0.0000001
+-2.5
+NaN
+inf
+-inf
+";
+
+    assert_eq!(common::run(source), expected);
+    assert_eq!(run_executable(&Program::new(source)), expected);
+}
+
+#[test]
+pub fn e2e_functions_named_like_c_symbols() {
+    let source = "\
+let puts x = x + 1
+let malloc x = x * 2
+let degu_main x = x * 3
+
+let add n = fn x -> x + n
+
+let main _ =
+    print_int (puts 1)
+    print_int (malloc 2)
+    print_int (degu_main 3)
+    print_int ((add 4) 5)
+";
+    let expected = "2\n4\n9\n9\n";
+
+    assert_eq!(common::run(source), expected);
+    assert_eq!(run_executable(&Program::new(source)), expected);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+#[test]
+pub fn e2e_closures_capture_their_environment() {
+    let output = common::run("\
+let make_adder n = fn x -> x + n
+let curry n = fn a -> fn b -> a + b + n
+let apply_twice f x = f (f x)
+
+let main _ =
+    let add3 = make_adder 3
+    print_int (add3 4)
+    print_int (apply_twice (fn x -> x * 2) 5)
+    let offset = 10
+    let shift = fn x -> x + offset
+    print_int (shift 1)
+    print_int ((curry 1) 2 3)
+");
+
+    assert_eq!(output, "7\n20\n11\n6\n");
+}
+
+#[test]
+pub fn e2e_lambdas_need_a_parameter() {
+    let errors = common::fail("let main _ = print_int ((fn -> 1) ())\n");
+    assert!(errors.contains("Anonymous functions must have at least one parameter"), "{}", errors);
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{Program, run_executable};
+
+#[test]
+pub fn e2e_conversions() {
+    let source = "\
+let main _ =
+    let c = 'é'
+    print_int (int_of_char c)
+    print_char (char_of_int 128512)
+    print_string (string_of_char 'a' + string_of_char '\\u{1F600}')
+    print_char (char_of_string \"€\")
+    print_int (int_of_char (char_of_string \"a\"))
+    print_int (int_of_float 2.9)
+    print_int (int_of_float (-2.9))
+    print_int (int_of_float (-9223372036854775808.0))
+    print_number (float_of_int 3)
+";
+    let expected = "233\n😀\na😀\n€\n97\n2\n-2\n-9223372036854775808\n3\n";
+
+    assert_eq!(common::run(source), expected);
+    assert_eq!(run_executable(&Program::new(source)), expected);
+}
+
+#[test]
+pub fn e2e_char_of_string_needs_a_single_character() {
+    for string in &["", "ab", "e\\u{301}"] {
+        let source = format!("let main _ = print_char (char_of_string \"{}\")\n", string);
+        let errors = common::fail(&source);
+        assert!(
+            errors.contains("panic: char_of_string: the string must hold exactly one character"),
+            "{}",
+            errors);
+    }
+}
+
+#[test]
+pub fn e2e_int_of_float_needs_a_value_in_range() {
+    for value in &["0.0 / 0.0", "1.0 / 0.0", "-1.0 / 0.0", "9223372036854775808.0"] {
+        let source = format!("let main _ = print_int (int_of_float ({}))\n", value);
+        let errors = common::fail(&source);
+        assert!(
+            errors.contains("panic: int_of_float: the value does not fit in an int"),
+            "{}",
+            errors);
+    }
+}
@@ -0,0 +1,48 @@
+mod common;
+
+#[test]
+pub fn e2e_partial_application() {
+    let output = common::run("\
+let add a b = a + b
+let compose f g x = f (g x)
+
+let main _ =
+    let inc = add 1
+    print_int (inc 41)
+    let double x = x * 2
+    print_int (compose inc double 5)
+    print_int (compose (add 10) (add 20) 0)
+    print_int ((add 1) 2)
+");
+
+    assert_eq!(output, "42\n11\n30\n3\n");
+}
+
+#[test]
+pub fn e2e_functions_as_arguments() {
+    let output = common::run("\
+let add a b = a + b
+let apply f = f 1 2
+let twice f x = f (f x)
+let inc x = x + 1
+
+let main _ =
+    print_int (apply add)
+    print_int (twice inc 0)
+    print_int (twice (twice inc) 0)
+");
+
+    assert_eq!(output, "3\n2\n4\n");
+}
+
+#[test]
+pub fn e2e_over_application() {
+    let output = common::run("\
+let pick a = fn b -> a - b
+
+let main _ =
+    print_int (pick 5 2)
+");
+
+    assert_eq!(output, "3\n");
+}
@@ -0,0 +1,66 @@
+mod common;
+
+#[test]
+pub fn e2e_integer_division() {
+    let output = common::run("\
+let half = 10 / 2
+let divide a b = a / b
+let remainder a b = a % b
+
+let main _ =
+    print_int half
+    print_int (divide 7 2)
+    print_int (remainder (-7) 2)
+    print_int (divide (-9223372036854775807 - 1) 2)
+    print_number (divide 1.0 0.0)
+");
+
+    assert_eq!(output, "5\n3\n-1\n-4611686018427387904\ninf\n");
+}
+
+#[test]
+pub fn e2e_negation() {
+    let output = common::run("\
+let dec n = n-1
+
+let main _ =
+    print_int (dec 5)
+    print_int (10-3-2)
+    print_int (-2 * 3)
+    print_int (- 4 + 1)
+    print_number (-1.5 * 2.0)
+    print_int (if !(1 > 2) then 1 else 0)
+");
+
+    assert_eq!(output, "4\n5\n-6\n-3\n-3\n1\n");
+}
+
+#[test]
+pub fn e2e_division_by_zero_panics() {
+    let errors = common::fail("\
+let divide a b = a / b
+let main _ = print_int (divide 1 0)
+");
+    assert!(errors.contains("panic: division by zero"), "{}", errors);
+
+    let errors = common::fail("\
+let remainder a b = a % b
+let main _ = print_int (remainder 1 0)
+");
+    assert!(errors.contains("panic: remainder by zero"), "{}", errors);
+}
+
+#[test]
+pub fn e2e_division_overflow_panics() {
+    let errors = common::fail("\
+let divide a b = a / b
+let main _ = print_int (divide (-9223372036854775807 - 1) (-1))
+");
+    assert!(errors.contains("panic: division overflow"), "{}", errors);
+
+    let errors = common::fail("\
+let remainder a b = a % b
+let main _ = print_int (remainder (-9223372036854775807 - 1) (-1))
+");
+    assert!(errors.contains("panic: remainder overflow"), "{}", errors);
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use std::fs;
+
+const SOURCE: &str = "\
+let id x = x
+let add a b = a + b
+let count xs =
+    match xs with
+    | [] -> 0
+    | _ :: rest -> 1 + count rest
+
+let main _ =
+    print_int (id 1)
+    print_string (id \"one\")
+    print_int (add 1 2)
+    print_number (add 1.5 2.0)
+    print_string (add \"a\" \"b\")
+    print_int (count [1; 2; 3])
+    print_int (count [\"a\"])
+    print_int (count [[1]; []])
+";
+
+#[test]
+pub fn e2e_polymorphic_functions() {
+    assert_eq!(common::run(SOURCE), "1\none\n3\n3.5\nab\n3\n1\n2\n");
+}
+
+#[test]
+pub fn e2e_an_instance_per_type() {
+    let program = common::Program::new(SOURCE);
+    let output = program.compiler(&["build", "--emit=ir", "main.dg"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let ir = fs::read_to_string(program.path().with_extension("ll")).unwrap();
+    for name in &["id$int", "id$string", "add$int", "add$float", "add$string"] {
+        assert!(ir.contains(&format!("degu.{}", name)), "{} is missing from\n{}", name, ir);
+    }
+}
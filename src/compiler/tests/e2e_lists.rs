@@ -0,0 +1,37 @@
+mod common;
+
+#[test]
+pub fn e2e_lists() {
+    let output = common::run("\
+let print_all xs = fold (fn _ x -> print_int x) () xs
+
+let main _ =
+    let xs = 1 :: 2 :: [3; 4]
+    print_all xs
+    print_int (length xs)
+    print_int (head xs)
+    print_int (length (tail xs))
+    print_all (filter (fn x -> x % 2 == 0) xs)
+    print_int (fold (fn sum x -> sum + x) 0 xs)
+    print_all ([0] @ xs |> map (fn x -> x * 10))
+    print_int (length (append [\"a\"] [\"b\"; \"c\"]))
+    print_int (0 :: xs |> length)
+");
+
+    assert_eq!(output, "1\n2\n3\n4\n4\n1\n3\n2\n4\n10\n0\n10\n20\n30\n40\n3\n5\n");
+}
+
+#[test]
+pub fn e2e_empty_list_panics() {
+    let errors = common::fail("\
+let first xs = head xs
+let main _ = print_int (first [])
+");
+    assert!(errors.contains("panic: head of an empty list"), "{}", errors);
+
+    let errors = common::fail("\
+let rest xs = tail xs
+let main _ = print_int (length (rest []))
+");
+    assert!(errors.contains("panic: tail of an empty list"), "{}", errors);
+}
@@ -0,0 +1,23 @@
+mod common;
+
+#[test]
+pub fn e2e_and_or_short_circuit() {
+    let output = common::run("\
+let show b = if b then print_string \"true\" else print_string \"false\"
+
+let check x =
+    print_int x
+    x > 0
+
+let main _ =
+    let a = 0
+    show (a != 0 and 10 / a > 1)
+    show (a == 0 or 10 / a > 1)
+    show (check 0 and check 1)
+    show (check 1 or check 2)
+    show (check 1 and check 2)
+    show (check 0 or check 2)
+");
+
+    assert_eq!(output, "false\ntrue\n0\nfalse\n1\ntrue\n1\n2\ntrue\n0\n2\ntrue\n");
+}
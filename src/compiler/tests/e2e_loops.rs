@@ -0,0 +1,36 @@
+mod common;
+
+#[test]
+pub fn e2e_for_loops() {
+    let output = common::run("\
+let main _ =
+    for i in 1..3 do
+        let square = i * i
+        print_int square
+    let n = 3
+    for i in n-1..n do print_int (i * 10)
+    for i in 2..2 do print_int i
+    for i in 5..4 do print_int i
+");
+
+    assert_eq!(output, "1\n4\n9\n20\n30\n2\n");
+}
+
+#[test]
+pub fn e2e_loop_variables_are_scoped_to_the_loop() {
+    let output = common::run("\
+let main _ =
+    let i = 100
+    for i in 1..2 do print_int i
+    print_int i
+");
+    assert_eq!(output, "1\n2\n100\n");
+
+    let errors = common::fail("\
+let main _ =
+    for i in 1..3 do print_int i
+    print_int i
+");
+    assert!(errors.contains("Unknown name \"i\""), "{}", errors);
+    assert!(errors.contains("main.dg:3:15"), "{}", errors);
+}
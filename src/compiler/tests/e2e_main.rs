@@ -0,0 +1,17 @@
+mod common;
+
+#[test]
+pub fn e2e_main_with_a_named_parameter() {
+    assert_eq!(common::run("let main args = print_int 1\n"), "1\n");
+    assert_eq!(common::run("let main args = args\n"), "");
+    assert_eq!(common::run("let main (args: unit) = args\n"), "");
+}
+
+#[test]
+pub fn e2e_main_parameter_must_be_unit() {
+    let errors = common::fail("let main x = print_int x\n");
+    assert!(errors.contains("The main function must take a single parameter of type unit"));
+
+    let errors = common::fail("let main a b = print_int 1\n");
+    assert!(errors.contains("The main function must take a single parameter of type unit"));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+#[test]
+pub fn e2e_match() {
+    let output = common::run("\
+let describe xs =
+    match xs with
+    | [] -> \"empty\"
+    | [_] -> \"one\"
+    | _ :: _ -> \"many\"
+
+let sign n =
+    match n with
+    | 0 -> 0
+    | n -> if n < 0 then -1 else 1
+
+let sum_pairs ps =
+    match ps with
+    | [] -> 0
+    | (a, b) :: rest -> a + b + sum_pairs rest
+
+let greet name =
+    match name with
+    | \"world\" -> \"hello world\"
+    | other -> \"hi \" + other
+
+let main _ =
+    print_string (describe [])
+    print_string (describe [1])
+    print_string (describe [\"a\"; \"b\"])
+    print_int (sign (-5))
+    print_int (sign 0)
+    print_int (sum_pairs [(1, 2); (3, 4)])
+    print_string (greet \"world\")
+    print_string (greet \"you\")
+");
+
+    assert_eq!(output, "empty\none\nmany\n-1\n0\n10\nhello world\nhi you\n");
+}
+
+#[test]
+pub fn e2e_incomplete_match_panics() {
+    let errors = common::fail("\
+let name n =
+    match n with
+    | 1 -> \"one\"
+    | 2 -> \"two\"
+
+let main _ = print_string (name 3)
+");
+    assert!(errors.contains("panic: incomplete match at"), "{}", errors);
+}
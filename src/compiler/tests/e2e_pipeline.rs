@@ -0,0 +1,54 @@
+mod common;
+
+#[test]
+pub fn e2e_pipeline() {
+    let output = common::run("\
+let add a b = a + b
+let double x = x * 2
+
+let main _ =
+    1 |> add 2 |> double |> print_int
+    let quadruple = double >> double
+    print_int (quadruple 3)
+    5 |> (add 1 >> double) |> print_int
+");
+
+    assert_eq!(output, "6\n12\n12\n");
+}
+
+#[test]
+pub fn e2e_pipelines_across_lines() {
+    let output = common::run("\
+let double x = x * 2
+
+let main _ =
+    [1; 2; 3]
+    |> map double
+    |> fold (fn acc x -> acc + x) 0
+    |> print_int
+    4
+        |> double
+        |> print_int
+");
+
+    assert_eq!(output, "12\n8\n");
+}
+
+#[test]
+pub fn e2e_composition_evaluates_its_operands_once() {
+    let output = common::run("\
+let trace name f =
+    print_string name
+    f
+
+let double x = x * 2
+let inc x = x + 1
+
+let main _ =
+    let h = trace \"f\" double >> trace \"g\" inc
+    print_int (h 1)
+    print_int (h 2)
+");
+
+    assert_eq!(output, "f\ng\n3\n5\n");
+}
@@ -0,0 +1,25 @@
+mod common;
+
+#[test]
+pub fn e2e_records() {
+    let output = common::run("\
+type Point = { x: float; y: float }
+type Person = { name: string; age: int }
+
+let norm p = p.x * p.x + p.y * p.y
+let older p = { p with age = p.age + 1 }
+
+let main _ =
+    let p = { x = 3.0; y = 4.0 }
+    print_number (norm p)
+    let q = { p with y = 0.5 }
+    print_number q.x
+    print_number q.y
+    print_number p.y
+    let bob = older { name = \"Bob\"; age = 41 }
+    print_string bob.name
+    print_int bob.age
+");
+
+    assert_eq!(output, "25\n3\n0.5\n4\nBob\n42\n");
+}
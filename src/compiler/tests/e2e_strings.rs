@@ -0,0 +1,42 @@
+mod common;
+
+use common::{Program, run_executable};
+
+#[test]
+pub fn e2e_strings() {
+    let source = "\
+let greet name = \"Hello, \" + name + \"!\"
+let yes b = if b then 1 else 0
+
+let main _ =
+    let s = greet \"wörld\"
+    print_string s
+    print_int (string_length s)
+    print_int (string_length \"\")
+    print_int (string_length \"\\u{1F600}\")
+    print_string (\"tab\\there\" + \"\\n\" + \"quote \\\"q\\\" and \\\\\")
+    print_int (yes (\"abc\" < \"abd\"))
+    print_int (yes (\"b\" > \"abc\"))
+    print_int (yes (\"abc\" == \"ab\" + \"c\"))
+    print_int (yes (\"abc\" != \"abc\"))
+    print_int (yes (\"ab\" <= \"ab\"))
+    print_int (yes (\"\" >= \"a\"))
+";
+    let expected = "\
+Hello, wörld!
+13
+0
+1
+tab\there
+quote \"q\" and \\
+1
+1
+1
+0
+1
+0
+";
+
+    assert_eq!(common::run(source), expected);
+    assert_eq!(run_executable(&Program::new(source)), expected);
+}
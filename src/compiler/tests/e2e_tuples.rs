@@ -0,0 +1,23 @@
+mod common;
+
+#[test]
+pub fn e2e_tuples() {
+    let output = common::run("\
+let divide a b = (a / b, a % b)
+let swap p =
+    let (a, b) = p
+    (b, a)
+
+let main _ =
+    let (q, r) = divide 17 5
+    print_int q
+    print_int r
+    let (s, n) = swap (1, \"one\")
+    print_string s
+    print_int n
+    let ((a, b), _) = ((3, 4), 5)
+    print_int (a * b)
+");
+
+    assert_eq!(output, "3\n2\none\n1\n12\n");
+}
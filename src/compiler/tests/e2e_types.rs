@@ -0,0 +1,25 @@
+mod common;
+
+#[test]
+pub fn e2e_typed_codegen() {
+    let output = common::run("\
+let fact n = if n < 2 then 1 else n * fact (n - 1)
+let half x = x / 2.0
+let positive x = x > 0
+
+let main _ =
+    print_int (fact 10)
+    print_number (half 5.0)
+    print_int (if positive (fact 3 - 6) then 1 else 0)
+");
+
+    assert_eq!(output, "3628800\n2.5\n0\n");
+}
+
+#[test]
+pub fn e2e_type_errors_are_reported() {
+    let errors = common::fail("let main _ = print_int 1.5\n");
+
+    assert!(errors.contains("error[E0100]: Expected type `int` but found `float`"), "{}", errors);
+    assert!(errors.contains("main.dg:1:24"), "{}", errors);
+}
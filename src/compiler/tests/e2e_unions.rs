@@ -0,0 +1,26 @@
+mod common;
+
+#[test]
+pub fn e2e_unions() {
+    let output = common::run("\
+type Shape =
+    | Circle of float
+    | Rect of float * float
+    | Empty
+
+let area s =
+    match s with
+    | Circle r -> 3.0 * r * r
+    | Rect w h -> w * h
+    | Empty -> 0.0
+
+let main _ =
+    print_number (area (Circle 1.0))
+    print_number (area (Rect 2.0 3.5))
+    print_number (area Empty)
+    let shapes = map (Rect 1.0) [2.0; 3.0]
+    print_number (fold (fn sum s -> sum + area s) 0.0 shapes)
+");
+
+    assert_eq!(output, "3\n7\n0\n5\n");
+}
@@ -11,6 +11,7 @@ use std::ffi::CStr;
 use super::llvm_ref::LlvmRef;
 use super::value::{AnyValue, Function};
 
+#[derive(Clone)]
 pub struct BasicBlock {
     pub(crate) ptr: LLVMBasicBlockRef,
 }
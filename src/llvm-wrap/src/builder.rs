@@ -1,26 +1,50 @@
 use libc::c_uint;
 
-use llvm::LLVMRealPredicate;
+use llvm::{LLVMIntPredicate, LLVMRealPredicate};
 use llvm::core::{
+    LLVMAddCase,
     LLVMBuildAdd,
+    LLVMBuildBitCast,
     LLVMBuildBr,
     LLVMBuildCall,
     LLVMBuildCondBr,
+    LLVMBuildExtractValue,
     LLVMBuildFAdd,
     LLVMBuildFCmp,
+    LLVMBuildFDiv,
     LLVMBuildFMul,
+    LLVMBuildFNeg,
+    LLVMBuildFRem,
     LLVMBuildFSub,
+    LLVMBuildFPToSI,
+    LLVMBuildICmp,
+    LLVMBuildInsertValue,
+    LLVMBuildLoad,
+    LLVMBuildMalloc,
     LLVMBuildMul,
+    LLVMBuildNeg,
+    LLVMBuildNot,
     LLVMBuildPhi,
     LLVMBuildRet,
     LLVMBuildRetVoid,
+    LLVMBuildSDiv,
+    LLVMBuildSIToFP,
+    LLVMBuildSRem,
+    LLVMBuildStore,
+    LLVMBuildStructGEP,
     LLVMBuildSub,
+    LLVMBuildSwitch,
     LLVMBuildUIToFP,
+    LLVMBuildUnreachable,
+    LLVMBuildZExt,
+    LLVMClearInsertionPosition,
     LLVMConstInt,
+    LLVMConstNull,
     LLVMConstReal,
     LLVMCreateBuilderInContext,
     LLVMDisposeBuilder,
     LLVMGetInsertBlock,
+    LLVMGetUndef,
     LLVMPositionBuilderAtEnd,
 };
 use llvm::prelude::LLVMBool;
@@ -94,6 +118,41 @@ impl RealPredicate {
     }
 }
 
+/// Comparison predicates for integers, signed and unsigned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntPredicate {
+    EQ,
+    NE,
+    UGT,
+    UGE,
+    ULT,
+    ULE,
+    SGT,
+    SGE,
+    SLT,
+    SLE,
+}
+
+impl IntPredicate {
+    fn to_llvm(self) -> LLVMIntPredicate {
+        use LLVMIntPredicate::*;
+        use IntPredicate::*;
+
+        match self {
+            EQ => LLVMIntEQ,
+            NE => LLVMIntNE,
+            UGT => LLVMIntUGT,
+            UGE => LLVMIntUGE,
+            ULT => LLVMIntULT,
+            ULE => LLVMIntULE,
+            SGT => LLVMIntSGT,
+            SGE => LLVMIntSGE,
+            SLT => LLVMIntSLT,
+            SLE => LLVMIntSLE,
+        }
+    }
+}
+
 impl Drop for Builder {
     fn drop(&mut self) {
         unsafe {
@@ -138,6 +197,13 @@ impl Builder {
         }
     }
 
+    /// Makes the builder not insert anywhere. Only constants can be built afterwards.
+    pub fn clear_insertion_position(&mut self) {
+        unsafe {
+            LLVMClearInsertionPosition(self.ptr);
+        }
+    }
+
     pub fn build_const_int(&mut self, ty: Type, value: u64, signed: bool) -> AnyValue {
         unsafe {
             AnyValue {
@@ -153,6 +219,25 @@ impl Builder {
             }
         }
     }
+
+    /// The all zeroes value of any type.
+    pub fn build_const_null(&mut self, ty: Type) -> AnyValue {
+        unsafe {
+            AnyValue {
+                ptr: LLVMConstNull(ty.llvm_ref())
+            }
+        }
+    }
+
+    /// Unspecified value of any type, a starting point for building aggregates.
+    pub fn build_undef(&mut self, ty: Type) -> AnyValue {
+        unsafe {
+            AnyValue {
+                ptr: LLVMGetUndef(ty.llvm_ref())
+            }
+        }
+    }
+
     pub fn build_ret_void(&mut self) -> AnyValue {
         unsafe {
             AnyValue {
@@ -199,6 +284,27 @@ impl Builder {
         })
     }
 
+    /// Calls a function pointer. Unlike `build_call` the arguments are not checked.
+    pub fn build_indirect_call(
+        &mut self,
+        callee: &AnyValue,
+        args: &[AnyValue],
+        name: Option<&CStr>) -> AnyValue
+    {
+        let args = args.into_iter().map(LlvmRef::llvm_ref).collect::<Vec<_>>();
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildCall(
+                    self.ptr,
+                    callee.llvm_ref(),
+                    args.as_ptr() as *mut _,
+                    args.len() as c_uint,
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            },
+        }
+    }
+
     pub fn build_cast_uint_to_fp(
         &mut self,
         value: AnyValue,
@@ -217,12 +323,154 @@ impl Builder {
         }
     }
 
+    pub fn build_cast_int_to_fp(
+        &mut self,
+        value: &AnyValue,
+        ty: Type,
+        name: Option<&CStr>) -> AnyValue
+    {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildSIToFP(
+                    self.ptr,
+                    value.llvm_ref(),
+                    ty.llvm_ref(),
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            }
+        }
+    }
+
+    /// Truncates towards zero into a signed integer.
+    pub fn build_cast_fp_to_int(
+        &mut self,
+        value: &AnyValue,
+        ty: Type,
+        name: Option<&CStr>) -> AnyValue
+    {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildFPToSI(
+                    self.ptr,
+                    value.llvm_ref(),
+                    ty.llvm_ref(),
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            }
+        }
+    }
+
+    pub fn build_bitcast(&mut self, value: &AnyValue, ty: Type, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildBitCast(
+                    self.ptr,
+                    value.llvm_ref(),
+                    ty.llvm_ref(),
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            }
+        }
+    }
+
+    /// Allocates a `ty` on the heap with `malloc` and returns a pointer to it.
+    pub fn build_malloc(&mut self, ty: Type, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildMalloc(self.ptr, ty.llvm_ref(), name.map_or(EMPTY_C_STR, CStr::as_ptr))
+            },
+        }
+    }
+
+    pub fn build_load(&mut self, ptr: &AnyValue, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildLoad(self.ptr, ptr.llvm_ref(), name.map_or(EMPTY_C_STR, CStr::as_ptr))
+            },
+        }
+    }
+
+    pub fn build_store(&mut self, value: &AnyValue, ptr: &AnyValue) -> AnyValue {
+        AnyValue {
+            ptr: unsafe { LLVMBuildStore(self.ptr, value.llvm_ref(), ptr.llvm_ref()) },
+        }
+    }
+
+    /// Pointer to the field `index` of the struct `ptr` points to.
+    pub fn build_struct_gep(&mut self, ptr: &AnyValue, index: u32, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildStructGEP(
+                    self.ptr,
+                    ptr.llvm_ref(),
+                    index as c_uint,
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            },
+        }
+    }
+
+    /// Copy of the aggregate `agg` with the element `index` replaced by `value`.
+    pub fn build_insert_value(
+        &mut self,
+        agg: &AnyValue,
+        value: &AnyValue,
+        index: u32,
+        name: Option<&CStr>) -> AnyValue
+    {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildInsertValue(
+                    self.ptr,
+                    agg.llvm_ref(),
+                    value.llvm_ref(),
+                    index as c_uint,
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            },
+        }
+    }
+
+    pub fn build_extract_value(&mut self, agg: &AnyValue, index: u32, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildExtractValue(
+                    self.ptr,
+                    agg.llvm_ref(),
+                    index as c_uint,
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            },
+        }
+    }
+
+    /// Widens an integer filling the new bits with zeroes.
+    pub fn build_zext(&mut self, value: &AnyValue, ty: Type, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildZExt(
+                    self.ptr,
+                    value.llvm_ref(),
+                    ty.llvm_ref(),
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            }
+        }
+    }
+
     pub fn build_branch(&mut self, dest: &BasicBlock) -> AnyValue {
         AnyValue {
             ptr: unsafe { LLVMBuildBr(self.ptr, dest.llvm_ref()) },
         }
     }
 
+    /// Terminates a block control never reaches, like the one after a call that exits.
+    pub fn build_unreachable(&mut self) -> AnyValue {
+        AnyValue {
+            ptr: unsafe { LLVMBuildUnreachable(self.ptr) },
+        }
+    }
+
     pub fn build_conditional_branch(
         &mut self,
         cond: &AnyValue,
@@ -236,6 +484,28 @@ impl Builder {
         }
     }
 
+    /// Jumps to the block of the constant equal to `value` or to `default` when there is none.
+    pub fn build_switch(
+        &mut self,
+        value: &AnyValue,
+        default: &BasicBlock,
+        cases: &[(AnyValue, BasicBlock)]) -> AnyValue
+    {
+        unsafe {
+            let switch = LLVMBuildSwitch(
+                self.ptr,
+                value.llvm_ref(),
+                default.llvm_ref(),
+                cases.len() as c_uint);
+
+            for (constant, dest) in cases {
+                LLVMAddCase(switch, constant.llvm_ref(), dest.llvm_ref());
+            }
+
+            AnyValue { ptr: switch }
+        }
+    }
+
     pub fn build_phi(&mut self, ty: Type, name: Option<&CStr>) -> Phi {
         Phi {
             value: AnyValue {
@@ -266,15 +536,63 @@ impl Builder {
         }
     }
 
+    pub fn build_int_cmp(
+        &mut self,
+        op: IntPredicate,
+        a: &AnyValue,
+        b: &AnyValue,
+        name: Option<&CStr>) -> AnyValue
+    {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildICmp(
+                    self.ptr,
+                    op.to_llvm(),
+                    a.llvm_ref(),
+                    b.llvm_ref(),
+                    name.map_or(EMPTY_C_STR, CStr::as_ptr),
+                )
+            },
+        }
+    }
+
     impl_bin_op!{
         build_add => LLVMBuildAdd,
         build_sub => LLVMBuildSub,
         build_mul => LLVMBuildMul,
+        build_sdiv => LLVMBuildSDiv,
+        build_srem => LLVMBuildSRem,
     }
 
     impl_bin_op!{
         build_fp_add => LLVMBuildFAdd,
         build_fp_sub => LLVMBuildFSub,
         build_fp_mul => LLVMBuildFMul,
+        build_fp_div => LLVMBuildFDiv,
+        build_fp_rem => LLVMBuildFRem,
+    }
+
+    pub fn build_not(&mut self, value: &AnyValue, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildNot(self.ptr, value.llvm_ref(), name.map_or(EMPTY_C_STR, CStr::as_ptr))
+            },
+        }
+    }
+
+    pub fn build_neg(&mut self, value: &AnyValue, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildNeg(self.ptr, value.llvm_ref(), name.map_or(EMPTY_C_STR, CStr::as_ptr))
+            },
+        }
+    }
+
+    pub fn build_fp_neg(&mut self, value: &AnyValue, name: Option<&CStr>) -> AnyValue {
+        AnyValue {
+            ptr: unsafe {
+                LLVMBuildFNeg(self.ptr, value.llvm_ref(), name.map_or(EMPTY_C_STR, CStr::as_ptr))
+            },
+        }
     }
 }
@@ -1,6 +1,8 @@
 use libc::c_uint;
 
 use llvm::core::{
+    LLVMConstNamedStruct,
+    LLVMConstStructInContext,
     LLVMContextCreate,
     LLVMContextDispose,
     LLVMDoubleTypeInContext,
@@ -9,9 +11,13 @@ use llvm::core::{
     LLVMFunctionType,
     LLVMInt128TypeInContext,
     LLVMInt16TypeInContext,
+    LLVMInt1TypeInContext,
     LLVMInt32TypeInContext,
     LLVMInt64TypeInContext,
     LLVMInt8TypeInContext,
+    LLVMPointerType,
+    LLVMStructCreateNamed,
+    LLVMStructTypeInContext,
     LLVMVoidTypeInContext,
 };
 use llvm::prelude::LLVMBool;
@@ -23,6 +29,7 @@ use super::builder::Builder;
 use super::llvm_ref::LlvmRef;
 use super::module::Module;
 use super::types::Type;
+use super::value::AnyValue;
 
 pub struct Context {
     pub(crate) ptr: <Self as LlvmRef>::Ref,
@@ -68,6 +75,7 @@ macro_rules! impl_basic_types {
 
 impl_basic_types! {
     void_type => LLVMVoidTypeInContext,
+    i1_type => LLVMInt1TypeInContext,
     i8_type => LLVMInt8TypeInContext,
     i16_type => LLVMInt16TypeInContext,
     i32_type => LLVMInt32TypeInContext,
@@ -93,4 +101,63 @@ impl Context {
             }
         }
     }
+
+    /// Pointer to `element` in the default address space.
+    pub fn pointer_type(element: Type) -> Type {
+        unsafe {
+            Type {
+                ptr: LLVMPointerType(element.ptr, 0),
+            }
+        }
+    }
+
+    /// Creates an anonymous (structurally typed) struct.
+    pub fn struct_type(&self, fields: &[Type], packed: bool) -> Type {
+        unsafe {
+            Type {
+                ptr: LLVMStructTypeInContext(
+                    self.ptr,
+                    fields.as_ptr() as *mut _,
+                    fields.len() as c_uint,
+                    packed as LLVMBool,
+                ),
+            }
+        }
+    }
+
+    /// Creates a struct identified by its name. Its body is set later so it can refer to itself.
+    pub fn named_struct_type(&self, name: &CStr) -> Type {
+        unsafe {
+            Type {
+                ptr: LLVMStructCreateNamed(self.ptr, name.as_ptr()),
+            }
+        }
+    }
+
+    /// Constant of a named struct, the values must match its fields.
+    pub fn const_named_struct(ty: Type, values: &[AnyValue]) -> AnyValue {
+        let mut values = values.into_iter().map(LlvmRef::llvm_ref).collect::<Vec<_>>();
+
+        AnyValue {
+            ptr: unsafe {
+                LLVMConstNamedStruct(ty.ptr, values.as_mut_ptr(), values.len() as c_uint)
+            },
+        }
+    }
+
+    /// Constant of an anonymous struct with the types of the values.
+    pub fn const_struct(&self, values: &[AnyValue], packed: bool) -> AnyValue {
+        let mut values = values.into_iter().map(LlvmRef::llvm_ref).collect::<Vec<_>>();
+
+        AnyValue {
+            ptr: unsafe {
+                LLVMConstStructInContext(
+                    self.ptr,
+                    values.as_mut_ptr(),
+                    values.len() as c_uint,
+                    packed as LLVMBool,
+                )
+            },
+        }
+    }
 }
@@ -11,25 +11,44 @@ use llvm::target::{
     LLVM_InitializeNativeAsmPrinter,
     LLVM_InitializeNativeTarget,
 };
+use llvm::core::LLVMDisposeMessage;
 use llvm::target_machine::{
-    LLVMTargetMachineRef,
+    LLVMCodeGenFileType,
+    LLVMCodeGenOptLevel,
+    LLVMCodeModel,
     LLVMCreateTargetDataLayout,
+    LLVMCreateTargetMachine,
+    LLVMDisposeTargetMachine,
+    LLVMGetDefaultTargetTriple,
+    LLVMGetTargetFromTriple,
+    LLVMRelocMode,
+    LLVMTargetMachineEmitToFile,
+    LLVMTargetMachineRef,
 };
 
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::ptr;
 use std::mem;
 use std::ops::Drop;
 
 use super::module::Module;
 use super::llvm_ref::LlvmRef;
+use super::transformation::OptLevel;
+use super::util::EMPTY_C_STR;
 use super::value::AnyValue;
 
 pub fn initialize_jit() {
+    unsafe {
+        LLVMLinkInMCJIT();
+    }
+
+    initialize_native_target();
+}
+
+pub fn initialize_native_target() {
     use std::process;
 
     unsafe {
-        LLVMLinkInMCJIT();
         if LLVM_InitializeNativeTarget() == 1 {
             process::exit(1);
         }
@@ -48,6 +67,24 @@ pub struct ExecutionEngine {
 
 pub struct TargetMachine {
     pub(crate) ptr: LLVMTargetMachineRef,
+    // Target machines borrowed from an execution engine are disposed by it.
+    owned: bool,
+    triple: CString,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CodeGenFileType {
+    Assembly,
+    Object,
+}
+
+impl CodeGenFileType {
+    fn to_llvm(self) -> LLVMCodeGenFileType {
+        match self {
+            CodeGenFileType::Assembly => LLVMCodeGenFileType::LLVMAssemblyFile,
+            CodeGenFileType::Object => LLVMCodeGenFileType::LLVMObjectFile,
+        }
+    }
 }
 
 pub struct TargetData {
@@ -62,6 +99,16 @@ impl Drop for ExecutionEngine {
     }
 }
 
+impl Drop for TargetMachine {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe {
+                LLVMDisposeTargetMachine(self.ptr);
+            }
+        }
+    }
+}
+
 impl ExecutionEngine {
     pub fn new(module: Module) -> Result<Self, String> {
         let ee = &mut ptr::null_mut();
@@ -99,11 +146,87 @@ impl ExecutionEngine {
             ptr: unsafe {
                 LLVMGetExecutionEngineTargetMachine(self.ptr)
             },
+            owned: false,
+            triple: CString::default(),
         }
     }
 }
 
 impl TargetMachine {
+    /// Creates a position independent target machine for the host triple.
+    ///
+    /// Requires `initialize_native_target` to have been called.
+    pub fn host(opt_level: OptLevel) -> Result<Self, String> {
+        unsafe {
+            let raw_triple = LLVMGetDefaultTargetTriple();
+            let triple = CStr::from_ptr(raw_triple).to_owned();
+            LLVMDisposeMessage(raw_triple);
+
+            let target = &mut ptr::null_mut();
+            let err = &mut ptr::null_mut();
+
+            if LLVMGetTargetFromTriple(triple.as_ptr(), target, err) != 0 {
+                let message = CStr::from_ptr(*err).to_str().unwrap().to_string();
+                LLVMDisposeMessage(*err);
+                return Err(message);
+            }
+
+            let level = match opt_level {
+                OptLevel::O0 => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
+                OptLevel::O1 => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
+                OptLevel::O2 => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+                OptLevel::O3 => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive,
+            };
+
+            let ptr = LLVMCreateTargetMachine(
+                *target,
+                triple.as_ptr(),
+                EMPTY_C_STR,
+                EMPTY_C_STR,
+                level,
+                LLVMRelocMode::LLVMRelocPIC,
+                LLVMCodeModel::LLVMCodeModelDefault,
+            );
+
+            Ok(Self {
+                ptr,
+                owned: true,
+                triple,
+            })
+        }
+    }
+
+    pub fn triple(&self) -> &CStr {
+        &self.triple
+    }
+
+    pub fn emit_to_file(
+        &self,
+        module: &Module,
+        path: &CStr,
+        file_type: CodeGenFileType) -> Result<(), String>
+    {
+        let err = &mut ptr::null_mut();
+
+        unsafe {
+            let failed = LLVMTargetMachineEmitToFile(
+                self.ptr,
+                module.llvm_ref(),
+                path.as_ptr() as *mut _,
+                file_type.to_llvm(),
+                err,
+            ) != 0;
+
+            if failed {
+                let message = CStr::from_ptr(*err).to_str().unwrap().to_string();
+                LLVMDisposeMessage(*err);
+                Err(message)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
     pub fn create_data_layout(&mut self) -> TargetData {
         TargetData {
             ptr: unsafe {
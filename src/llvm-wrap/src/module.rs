@@ -1,10 +1,27 @@
+use libc::c_uint;
+
+use llvm::LLVMLinkage;
+use llvm::bit_writer::LLVMWriteBitcodeToFile;
 use llvm::core::{
     LLVMAddFunction,
+    LLVMAddGlobal,
+    LLVMConstInBoundsGEP,
+    LLVMConstInt,
+    LLVMConstStringInContext,
+    LLVMGetModuleContext,
     LLVMGetNamedFunction,
     LLVMDisposeModule,
+    LLVMInt32TypeInContext,
     LLVMModuleCreateWithNameInContext,
     LLVMPrintModuleToString,
+    LLVMSetGlobalConstant,
+    LLVMSetInitializer,
+    LLVMSetLinkage,
+    LLVMSetTarget,
+    LLVMSetUnnamedAddr,
+    LLVMTypeOf,
 };
+use llvm::prelude::LLVMBool;
 use llvm::target::LLVMSetModuleDataLayout;
 
 use std::ffi::CStr;
@@ -76,6 +93,56 @@ impl Module {
         }
     }
 
+    /// Adds a private global constant initialized with `value` and returns a pointer to it.
+    pub fn add_global_constant(&mut self, value: &AnyValue, name: Option<&CStr>) -> AnyValue {
+        unsafe {
+            let global = LLVMAddGlobal(
+                self.ptr,
+                LLVMTypeOf(value.llvm_ref()),
+                name.map_or(EMPTY_C_STR, CStr::as_ptr),
+            );
+            LLVMSetInitializer(global, value.llvm_ref());
+            LLVMSetGlobalConstant(global, true as LLVMBool);
+            LLVMSetLinkage(global, LLVMLinkage::LLVMPrivateLinkage);
+            LLVMSetUnnamedAddr(global, true as LLVMBool);
+
+            AnyValue {
+                ptr: global,
+            }
+        }
+    }
+
+    /// Adds a private constant holding the NUL terminated `value`.
+    ///
+    /// Returns a constant `i8*` to its first byte so it can be used anywhere, even outside of
+    /// functions.
+    pub fn add_global_string(&mut self, value: &[u8], name: Option<&CStr>) -> AnyValue {
+        unsafe {
+            let context = LLVMGetModuleContext(self.ptr);
+            let initializer = AnyValue {
+                ptr: LLVMConstStringInContext(
+                    context,
+                    value.as_ptr() as *const _,
+                    value.len() as c_uint,
+                    false as LLVMBool,
+                ),
+            };
+
+            let global = self.add_global_constant(&initializer, name);
+
+            let zero = LLVMConstInt(LLVMInt32TypeInContext(context), 0, false as LLVMBool);
+            let mut indices = [zero, zero];
+
+            AnyValue {
+                ptr: LLVMConstInBoundsGEP(
+                    global.llvm_ref(),
+                    indices.as_mut_ptr(),
+                    indices.len() as c_uint,
+                ),
+            }
+        }
+    }
+
     pub fn function_pass_manager_builder(&mut self) -> FunctionPassManagerBuilder {
         FunctionPassManagerBuilder::new(self)
     }
@@ -85,4 +152,20 @@ impl Module {
             LLVMSetModuleDataLayout(self.ptr, target_data_layout.llvm_ref())
         }
     }
+
+    pub fn set_target_triple(&mut self, triple: &CStr) {
+        unsafe {
+            LLVMSetTarget(self.ptr, triple.as_ptr())
+        }
+    }
+
+    pub fn write_bitcode_to_file(&self, path: &CStr) -> Result<(), String> {
+        let result = unsafe { LLVMWriteBitcodeToFile(self.ptr, path.as_ptr()) };
+
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(format!("Failed to write bitcode to {:?}", path))
+        }
+    }
 }
@@ -1,10 +1,21 @@
+use libc::c_uint;
+
 use llvm::core::{
     LLVMCreateFunctionPassManagerForModule,
+    LLVMCreatePassManager,
     LLVMDisposePassManager,
     LLVMInitializeFunctionPassManager,
     LLVMRunFunctionPassManager,
+    LLVMRunPassManager,
 };
 use llvm::prelude::LLVMPassManagerRef;
+use llvm::transforms::pass_manager_builder::{
+    LLVMPassManagerBuilderCreate,
+    LLVMPassManagerBuilderDispose,
+    LLVMPassManagerBuilderPopulateModulePassManager,
+    LLVMPassManagerBuilderSetOptLevel,
+    LLVMPassManagerBuilderUseInlinerWithThreshold,
+};
 use llvm::transforms::scalar::{
     LLVMAddCFGSimplificationPass,
     LLVMAddGVNPass,
@@ -18,10 +29,35 @@ use super::llvm_ref::LlvmRef;
 use super::module::Module;
 use super::value::Function;
 
+/// Optimization level as in `-O0` to `-O3`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+    O3,
+}
+
+impl OptLevel {
+    pub(crate) fn to_llvm(self) -> c_uint {
+        match self {
+            OptLevel::O0 => 0,
+            OptLevel::O1 => 1,
+            OptLevel::O2 => 2,
+            OptLevel::O3 => 3,
+        }
+    }
+}
+
 pub struct FunctionPassManager {
     pub(crate) ptr: LLVMPassManagerRef,
 }
 
+/// Whole module pass manager populated with the standard pipeline for a given `OptLevel`.
+pub struct ModulePassManager {
+    pub(crate) ptr: LLVMPassManagerRef,
+}
+
 pub struct FunctionPassManagerBuilder {
     pub(crate) ptr: <Self as LlvmRef>::Ref,
     done: bool,
@@ -35,6 +71,14 @@ impl Drop for FunctionPassManager {
     }
 }
 
+impl Drop for ModulePassManager {
+    fn drop(&mut self) {
+        unsafe {
+            LLVMDisposePassManager(self.ptr);
+        }
+    }
+}
+
 impl Drop for FunctionPassManagerBuilder {
     fn drop(&mut self) {
         if !self.done {
@@ -56,6 +100,36 @@ impl FunctionPassManager {
     }
 }
 
+impl ModulePassManager {
+    pub fn new(opt_level: OptLevel) -> Self {
+        unsafe {
+            let ptr = LLVMCreatePassManager();
+            let builder = LLVMPassManagerBuilderCreate();
+
+            LLVMPassManagerBuilderSetOptLevel(builder, opt_level.to_llvm());
+            if opt_level >= OptLevel::O2 {
+                // The same threshold clang uses for -O2 and -O3.
+                LLVMPassManagerBuilderUseInlinerWithThreshold(builder, 225);
+            }
+            LLVMPassManagerBuilderPopulateModulePassManager(builder, ptr);
+            LLVMPassManagerBuilderDispose(builder);
+
+            Self {
+                ptr,
+            }
+        }
+    }
+
+    /// Runs the pass manager on a module.
+    ///
+    /// Returns true if any of the passes modified the module.
+    pub fn run(&self, m: &mut Module) -> bool {
+        unsafe {
+            LLVMRunPassManager(self.ptr, m.llvm_ref()) != 0
+        }
+    }
+}
+
 impl FunctionPassManagerBuilder {
     pub(crate) fn new(m: &mut Module) -> Self {
         Self {
@@ -1,6 +1,25 @@
+use libc::c_uint;
+
+use llvm::core::LLVMStructSetBody;
+use llvm::prelude::LLVMBool;
+
 use super::llvm_ref::LlvmRef;
 
 #[derive(Clone, Copy)]
 pub struct Type {
     pub(crate) ptr: <Self as LlvmRef>::Ref,
 }
+
+impl Type {
+    /// Sets the fields of a struct created by `Context::named_struct_type`.
+    pub fn set_struct_body(&self, fields: &[Type], packed: bool) {
+        unsafe {
+            LLVMStructSetBody(
+                self.ptr,
+                fields.as_ptr() as *mut _,
+                fields.len() as c_uint,
+                packed as LLVMBool,
+            );
+        }
+    }
+}
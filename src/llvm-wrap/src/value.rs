@@ -7,6 +7,7 @@ use llvm::core::{
     LLVMGetParams,
     LLVMSetValueName,
     LLVMPrintValueToString,
+    LLVMTypeOf,
 };
 
 use std::ffi::CStr;
@@ -14,6 +15,7 @@ use std::fmt::{self, Debug, Formatter};
 
 use super::basic_block::BasicBlock;
 use super::llvm_ref::LlvmRef;
+use super::types::Type;
 
 // TODO: improve this module to make a better use of Rust's type system to guard
 // from invalid operations like integer add on floating point numbers and so on.
@@ -53,6 +55,12 @@ impl AnyValue {
     pub fn set_name(&mut self, name: &CStr) {
         unsafe { LLVMSetValueName(self.ptr, name.as_ptr()) }
     }
+
+    pub fn get_type(&self) -> Type {
+        Type {
+            ptr: unsafe { LLVMTypeOf(self.ptr) },
+        }
+    }
 }
 
 impl Function {
@@ -60,6 +68,10 @@ impl Function {
         &self.value
     }
 
+    pub fn set_name(&mut self, name: &CStr) {
+        self.value.set_name(name)
+    }
+
     pub fn to_value(self) -> AnyValue {
         self.value
     }
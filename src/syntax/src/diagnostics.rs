@@ -0,0 +1,392 @@
+//! Errors and warnings reported to the user.
+//!
+//! Spans are byte offsets into the source. Rendering turns them into line and column numbers and
+//! shows the lines they are on with the labelled parts underlined, the primary ones with `^`.
+
+use lexpar::lexer::Span;
+
+use std::fmt::{self, Write};
+
+/// Codes of the diagnostics, each kind of problem has its own.
+pub mod codes {
+    /// Malformed token like a string literal with an unknown escape.
+    pub const MALFORMED_TOKEN: &str = "E0001";
+    /// Characters no token starts with.
+    pub const UNKNOWN_TOKEN: &str = "E0002";
+    pub const UNEXPECTED_TOKEN: &str = "E0003";
+    pub const UNEXPECTED_EOF: &str = "E0004";
+    pub const TYPE_ERROR: &str = "E0100";
+    /// Valid program the compiler cannot generate code for.
+    pub const CODEGEN_ERROR: &str = "E0200";
+
+    pub const UNREACHABLE_ARM: &str = "W0100";
+    pub const INCOMPLETE_MATCH: &str = "W0101";
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Part of the source a diagnostic points at.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    /// Primary labels are where the problem is, the others give context.
+    pub primary: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message.into())
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message.into())
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            code: None,
+            message,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Renders the diagnostic for the file `name` with the contents `source` like
+    ///
+    /// ```text
+    /// error[E0100]: Expected type `int` but found `float`
+    ///   --> main.dg:3:9
+    ///   |
+    /// 3 |     x + 1.0
+    ///   |         ^^^
+    /// ```
+    pub fn render(&self, name: &str, source: &str) -> String {
+        let mut out = match self.code {
+            Some(code) => format!("{}[{}]: {}\n", self.severity, code, self.message),
+            None => format!("{}: {}\n", self.severity, self.message),
+        };
+
+        let mut labels = self.labels
+            .iter()
+            .map(|label| (position(source, label.span.lo), label))
+            .collect::<Vec<_>>();
+        labels.sort_by_key(|&((line, column), label)| (line, column, !label.primary));
+
+        let width = labels
+            .iter()
+            .map(|&((line, _), _)| line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(width);
+
+        let location = labels
+            .iter()
+            .find(|(_, label)| label.primary)
+            .or_else(|| labels.first());
+
+        if let Some(&((line, column), _)) = location {
+            writeln!(out, "{} --> {}:{}:{}", gutter, name, line, column).unwrap();
+        } else {
+            writeln!(out, "{} --> {}", gutter, name).unwrap();
+        }
+
+        if !labels.is_empty() {
+            writeln!(out, "{} |", gutter).unwrap();
+        }
+
+        let mut index = 0;
+        while index < labels.len() {
+            let line = (labels[index].0).0;
+            let text = line_text(source, labels[index].1.span.lo);
+            writeln!(out, "{:>width$} | {}", line, text, width = width).unwrap();
+
+            while index < labels.len() && (labels[index].0).0 == line {
+                let ((_, column), label) = labels[index];
+                let length = source
+                    .get(label.span.lo..label.span.hi)
+                    .map_or(0, |text| text.chars().count());
+                writeln!(out, "{} | {}", gutter, underline(text, column, length, label)).unwrap();
+                index += 1;
+            }
+        }
+
+        for note in &self.notes {
+            writeln!(out, "{} = note: {}", gutter, note).unwrap();
+        }
+
+        out
+    }
+}
+
+/// Line and column of a byte offset, both starting from 1. Columns count characters.
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut column = 1;
+
+    for (index, c) in source.char_indices() {
+        if index >= offset {
+            break;
+        }
+
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+
+    (line, column)
+}
+
+/// The line a byte offset is on without the line break.
+fn line_text(source: &str, offset: usize) -> &str {
+    let offset = offset.min(source.len());
+    let start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let end = source[start..].find('\n').map_or(source.len(), |index| start + index);
+
+    &source[start..end]
+}
+
+/// Marks the `length` characters of the label under its line. Spans going past the line are cut
+/// at its end and empty ones still get a single mark.
+fn underline(text: &str, column: usize, length: usize, label: &Label) -> String {
+    // Tabs are kept so the marks line up with the text above them.
+    let indent = text
+        .chars()
+        .take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+
+    let rest = text.chars().count().saturating_sub(column - 1);
+    let length = length.min(rest).max(1);
+    let mark = if label.primary { "^" } else { "-" };
+
+    let mut line = format!("{}{}", indent, mark.repeat(length));
+    if !label.message.is_empty() {
+        line.push(' ');
+        line.push_str(&label.message);
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(source: &str, text: &str) -> Span {
+        let lo = source.find(text).unwrap();
+        Span::new(lo, lo + text.len(), 0)
+    }
+
+    #[test]
+    fn primary_label() {
+        let source = "let f x =\n    x + 1.0\n";
+        let diagnostic = Diagnostic::error("Expected type `int` but found `float`")
+            .with_code(codes::TYPE_ERROR)
+            .with_label(span(source, "1.0"), "");
+
+        let expected = "\
+error[E0100]: Expected type `int` but found `float`
+  --> main.dg:2:9
+  |
+2 |     x + 1.0
+  |         ^^^
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn labels_on_different_lines_and_notes() {
+        let source = "match x with\n| _ -> 1\n| 0 -> 2\n";
+        let diagnostic = Diagnostic::warning("Unreachable arm")
+            .with_code(codes::UNREACHABLE_ARM)
+            .with_label(span(source, "| 0 -> 2"), "never matched")
+            .with_secondary_label(
+                span(source, "| _ -> 1"),
+                "the arms before match all of its values")
+            .with_note("remove the arm")
+            .with_note("or move it up");
+
+        let expected = "\
+warning[W0100]: Unreachable arm
+  --> main.dg:3:1
+  |
+2 | | _ -> 1
+  | -------- the arms before match all of its values
+3 | | 0 -> 2
+  | ^^^^^^^^ never matched
+  = note: remove the arm
+  = note: or move it up
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn labels_on_the_same_line_are_ordered_by_column() {
+        let source = "let y = a + b\n";
+        let diagnostic = Diagnostic::error("Cannot add")
+            .with_label(span(source, "b"), "this")
+            .with_secondary_label(span(source, "a"), "that");
+
+        let expected = "\
+error: Cannot add
+  --> main.dg:1:13
+  |
+1 | let y = a + b
+  |         - that
+  |             ^ this
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn spans_over_several_lines_are_cut_at_the_first() {
+        let source = "let f x =\n    x\n";
+        let diagnostic = Diagnostic::error("Unfinished")
+            .with_label(Span::new(6, source.len(), 0), "");
+
+        let expected = "\
+error: Unfinished
+  --> main.dg:1:7
+  |
+1 | let f x =
+  |       ^^^
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn spans_at_the_end_of_the_file() {
+        let source = "let x = (1 +";
+        let diagnostic = Diagnostic::error("Unexpected end of the item")
+            .with_code(codes::UNEXPECTED_EOF)
+            .with_label(Span::new(source.len(), source.len(), 0), "");
+
+        let expected = "\
+error[E0004]: Unexpected end of the item
+  --> main.dg:1:13
+  |
+1 | let x = (1 +
+  |             ^
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+
+        let source = "let x = (1 +\n";
+        let diagnostic = Diagnostic::error("Unexpected end of the item")
+            .with_label(Span::new(source.len(), source.len(), 0), "");
+
+        let expected = "\
+error: Unexpected end of the item
+  --> main.dg:2:1
+  |
+2 |\x20
+  | ^
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn gutter_fits_the_widest_line_number() {
+        let source = "a\na\na\na\na\na\na\na\nb = 1\nc = b\n";
+        let diagnostic = Diagnostic::error("Unknown name")
+            .with_label(Span::new(source.rfind('b').unwrap(), source.len() - 1, 0), "")
+            .with_secondary_label(span(source, "b"), "defined here");
+
+        let expected = "\
+error: Unknown name
+   --> main.dg:10:5
+   |
+ 9 | b = 1
+   | - defined here
+10 | c = b
+   |     ^
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn columns_count_characters_and_keep_tabs() {
+        let source = "\tlet é = x\n";
+        let diagnostic = Diagnostic::error("Unknown name").with_label(span(source, "x"), "");
+
+        let expected = "\
+error: Unknown name
+  --> main.dg:1:10
+  |
+1 | \tlet é = x
+  | \t        ^
+";
+        assert_eq!(diagnostic.render("main.dg", source), expected);
+    }
+
+    #[test]
+    fn diagnostics_without_labels() {
+        let diagnostic = Diagnostic::error("Cannot read x.dg: No such file")
+            .with_note("check the path");
+
+        let expected = "\
+error: Cannot read x.dg: No such file
+ --> x.dg
+ = note: check the path
+";
+        assert_eq!(diagnostic.render("x.dg", ""), expected);
+    }
+}
@@ -0,0 +1,57 @@
+/// Replaces the escape sequences in the contents of a string or char literal.
+///
+/// Supported are `\n`, `\r`, `\t`, `\0`, `\\`, `\'`, `\"` and `\u{...}` with up to six hex
+/// digits of a Unicode scalar value. The error describes the first invalid sequence.
+pub fn unescape(text: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            Some('u') => unescape_unicode(&mut chars)?,
+            Some(other) => return Err(format!("Unknown escape sequence `\\{}`", other)),
+            None => return Err("Unfinished escape sequence `\\`".to_string()),
+        };
+
+        result.push(escaped);
+    }
+
+    Ok(result)
+}
+
+/// Parses the `{...}` part of a `\u{...}` escape.
+fn unescape_unicode(chars: &mut std::str::Chars) -> Result<char, String> {
+    if chars.next() != Some('{') {
+        return Err("Expected `{` after `\\u`".to_string());
+    }
+
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => digits.push(c),
+            None => return Err("Unfinished unicode escape, expected `}`".to_string()),
+        }
+    }
+
+    if digits.is_empty() || digits.len() > 6 {
+        return Err(format!("Invalid unicode escape `\\u{{{}}}`", digits));
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(std::char::from_u32)
+        .ok_or_else(|| format!("Invalid unicode escape `\\u{{{}}}`", digits))
+}
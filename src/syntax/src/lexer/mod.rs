@@ -1,11 +1,117 @@
 pub mod token;
 
+mod escape;
+
 use lexpar::lex_rules;
 use lexpar::lexer::{Lexer, Span};
 use self::token::Token;
 
+use super::diagnostics::{Diagnostic, codes};
+
 pub type Term = (Span, Token);
 
+/// Malformed token such as a string literal with an unknown escape sequence.
+#[derive(Debug)]
+pub struct LexError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl From<LexError> for Diagnostic {
+    fn from(err: LexError) -> Self {
+        Diagnostic::error(err.message)
+            .with_code(codes::MALFORMED_TOKEN)
+            .with_label(err.span, "")
+    }
+}
+
+/// Text no token starts with. Adjacent unknown characters are reported together.
+#[derive(Debug)]
+pub struct UnknownToken {
+    pub span: Span,
+    pub text: String,
+}
+
+impl UnknownToken {
+    /// What was probably meant, mostly operators of other languages.
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match self.text.as_str() {
+            "&&" | "&" => Some("use `and`"),
+            "^" => Some("`^` is not an operator, use `+` to concatenate strings"),
+            "~" => Some("use `-` to negate a number"),
+            "#" => Some("comments start with `//`"),
+            "$" => Some("names are made of letters, digits and `_`"),
+            "\r" => Some("lines must end with `\\n` alone"),
+            _ => None,
+        }
+    }
+}
+
+impl From<UnknownToken> for Diagnostic {
+    fn from(token: UnknownToken) -> Self {
+        let diagnostic = Diagnostic::error(format!("Unknown token `{}`", token.text.escape_debug()))
+            .with_code(codes::UNKNOWN_TOKEN)
+            .with_label(token.span.clone(), "");
+
+        match token.suggestion() {
+            Some(suggestion) => diagnostic.with_note(suggestion),
+            None => diagnostic,
+        }
+    }
+}
+
+/// Collects the `Unknown` tokens, merging the ones right next to each other like `&&`.
+pub fn unknown_tokens(tokens: &[Term]) -> Vec<UnknownToken> {
+    let mut unknown: Vec<UnknownToken> = Vec::new();
+
+    for (span, token) in tokens {
+        let text = match token {
+            Token::Unknown(text) => text,
+            _ => continue,
+        };
+
+        match unknown.last_mut() {
+            Some(last) if last.span.hi == span.lo => {
+                last.span = last.span.clone().extend(span.hi);
+                last.text.push_str(text);
+            },
+            _ => unknown.push(UnknownToken {
+                span: span.clone(),
+                text: text.clone(),
+            }),
+        }
+    }
+
+    unknown
+}
+
+fn string_literal(span: Span, text: &str) -> Term {
+    match escape::unescape(text) {
+        // Strings are NUL terminated at run time so they would be silently cut.
+        Ok(ref value) if value.contains('\0') => {
+            (span, Token::Invalid("Strings cannot contain NUL characters".to_string()))
+        },
+        Ok(value) => (span, Token::DoubleQuote(value)),
+        Err(message) => (span, Token::Invalid(message)),
+    }
+}
+
+fn char_literal(span: Span, text: &str) -> Term {
+    let value = match escape::unescape(text) {
+        Ok(value) => value,
+        Err(message) => return (span, Token::Invalid(message)),
+    };
+
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => (span, Token::SingleQuote(c)),
+        (None, _) => (span, Token::Invalid("Empty character literal".to_string())),
+        _ => (span, Token::Invalid(format!(
+            "Character literals must contain exactly one character, found {:?}",
+            value))),
+    }
+}
+
 pub fn lexer() -> Lexer<(Span, Token)> {
     use self::Token::*;
 
@@ -24,9 +130,19 @@ pub fn lexer() -> Lexer<(Span, Token)> {
         r"\bdo\b"                       => |span, _, _| (span, KwDo),
         r"\bor\b"                       => |span, _, _| (span, KwOr),
         r"\band\b"                      => |span, _, _| (span, KwAnd),
+        r"\btrue\b"                     => |span, _, _| (span, KwTrue),
+        r"\bfalse\b"                    => |span, _, _| (span, KwFalse),
+        r"\btype\b"                     => |span, _, _| (span, KwType),
+        r"\bwith\b"                     => |span, _, _| (span, KwWith),
+        r"\bof\b"                       => |span, _, _| (span, KwOf),
+        r"\bmatch\b"                    => |span, _, _| (span, KwMatch),
 
         r"[_a-zA-Z][_a-zA-Z0-9]*"   => |span, text, _| (span, Ident(text.to_owned())),
-        r"-?[0-9]+(?:\.[0-9]+)?"    => |span, text, _| (span, Number(text.parse().unwrap())),
+        r"[0-9]+\.[0-9]+"           => |span, text, _| (span, Float(text.parse().unwrap())),
+        r"[0-9]+"                   => |span, text, _| match text.parse() {
+            Ok(value) => (span, Integer(value)),
+            Err(_) => (span, Invalid("Integer literals must fit in 64 bits".to_string())),
+        },
 
         r"\("                       => |span, _, _| (span, LParen),
         r"\)"                       => |span, _, _| (span, RParen),
@@ -39,13 +155,18 @@ pub fn lexer() -> Lexer<(Span, Token)> {
         r"\-"                       => |span, _, _| (span, Minus),
         r"\*"                       => |span, _, _| (span, Asterisk),
         r"/"                        => |span, _, _| (span, FSlash),
+        r"%"                        => |span, _, _| (span, Percent),
         r"=="                       => |span, _, _| (span, Eq),
         r"!="                       => |span, _, _| (span, NotEq),
+        r">>"                       => |span, _, _| (span, Compose),
         r">"                        => |span, _, _| (span, GreaterThan),
         r">="                       => |span, _, _| (span, GreaterEq),
         r"<"                        => |span, _, _| (span, LessThan),
         r"<="                       => |span, _, _| (span, LessEq),
         r"\.\."                     => |span, _, _| (span, Range),
+        r"\."                       => |span, _, _| (span, Dot),
+        r"::"                       => |span, _, _| (span, Cons),
+        r"@"                        => |span, _, _| (span, Append),
 
         r"!"                        => |span, _, _| (span, Excl),
 
@@ -53,9 +174,12 @@ pub fn lexer() -> Lexer<(Span, Token)> {
         r"="                        => |span, _, _| (span, Assign),
         r":"                        => |span, _, _| (span, Colon),
         r","                        => |span, _, _| (span, Comma),
+        r"\|>"                      => |span, _, _| (span, Pipeline),
         r"\|"                       => |span, _, _| (span, Pipe),
         r";"                        => |span, _, _| (span, Semicolon),
-        r"'((?:\\'|[^'])*)'"        => |span, _, text| (span, SingleQuote(text[0].to_owned())),
-        r#""((?:\\"|[^"])*)""#      => |span, _, text| (span, DoubleQuote(text[0].to_owned())),
+        r"'((?:\\.|[^'\\\n])*)'"    => |span, _, text| char_literal(span, &text[0]),
+        r"'[^'\n]*"                 => |span, _, _| (span, Invalid("Unterminated character literal".to_string())),
+        r#""((?:\\.|[^"\\\n])*)""#  => |span, _, text| string_literal(span, &text[0]),
+        r#""[^"\n]*"#               => |span, _, _| (span, Invalid("Unterminated string literal".to_string())),
     ], |span, text| (span, Unknown(text.to_owned())))
 }
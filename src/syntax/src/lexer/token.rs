@@ -10,10 +10,14 @@ pub enum Token {
     KwIf, KwThen, KwElse,
     KwFor, KwIn, KwDo,
     KwOr, KwAnd,
+    KwTrue, KwFalse,
+    KwType, KwWith, KwOf,
+    KwMatch,
 
     // Data
     Ident(String),
-    Number(f64),
+    Integer(i64),
+    Float(f64),
 
     // Brackets
     LParen, RParen,
@@ -22,11 +26,13 @@ pub enum Token {
 
     // Binary Operators
     Plus, Minus,
-    Asterisk, FSlash,
+    Asterisk, FSlash, Percent,
     Eq, NotEq,
     GreaterThan, GreaterEq,
     LessThan, LessEq,
     Range,
+    Pipeline, Compose,
+    Cons, Append,
 
     // Unary Operators
     Excl,
@@ -36,14 +42,17 @@ pub enum Token {
     Assign,
     Colon,
     Comma,
+    Dot,
     Pipe,
     Semicolon,
-    SingleQuote(String),
+    SingleQuote(char),
     DoubleQuote(String),
 
     // Control (non-lex tokens)
 
     BlockStart, BlockEnd, BlockCont,
 
-    Unknown(String)
+    Unknown(String),
+    /// Malformed token, holds the error message.
+    Invalid(String),
 }
@@ -1,2 +1,3 @@
+pub mod diagnostics;
 pub mod lexer;
 pub mod parser;
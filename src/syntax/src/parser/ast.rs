@@ -1,24 +1,180 @@
 use lexpar::lexer::Span;
 
-#[derive(Debug)]
+/// Identifier of a node, unique within its tree. Later passes use it to attach information to
+/// the tree.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug)]
 pub struct AstNode {
+    pub id: NodeId,
     pub span: Span,
     pub expr: Box<Ast>,
 }
 
 impl AstNode {
+    /// The node gets its id when the tree it ends up in is numbered.
     pub fn new(span: Span, ast: Ast) -> Self {
         Self {
+            id: NodeId(0),
             span,
             expr: Box::new(ast),
         }
     }
+
+    /// Numbers the node and every node in it starting from 0. `Parser::parse` numbers the items
+    /// it returns, a tree built out of them like a block of all the items is numbered again.
+    pub fn number_nodes(&mut self) {
+        self.number(&mut 0);
+    }
+
+    pub(crate) fn number(&mut self, next: &mut usize) {
+        self.id = NodeId(*next);
+        *next += 1;
+
+        match &mut *self.expr {
+            Ast::Integer(_) | Ast::Float(_) | Ast::Bool(_) | Ast::Char(_) | Ast::Str(_) => {},
+            Ast::Ref(_) | Ast::Type { .. } | Ast::Error => {},
+            Ast::Tuple(nodes) | Ast::List(nodes) | Ast::Block(nodes) => {
+                for node in nodes {
+                    node.number(next);
+                }
+            },
+            Ast::Function { body, .. } | Ast::Lambda { body, .. } => body.number(next),
+            Ast::Call { args, .. } => {
+                for arg in args {
+                    arg.number(next);
+                }
+            },
+            Ast::Apply { callee, args } => {
+                callee.number(next);
+                for arg in args {
+                    arg.number(next);
+                }
+            },
+            Ast::Variable { expr, .. } | Ast::Unary { expr, .. } | Ast::Field { expr, .. } => {
+                expr.number(next)
+            },
+            Ast::Record { fields } => {
+                for (_, value) in fields {
+                    value.number(next);
+                }
+            },
+            Ast::Update { record, fields } => {
+                record.number(next);
+                for (_, value) in fields {
+                    value.number(next);
+                }
+            },
+            Ast::BinOp { lhs, rhs, .. } => {
+                lhs.number(next);
+                rhs.number(next);
+            },
+            Ast::If { condition, then, el } => {
+                condition.number(next);
+                then.number(next);
+                if let Some(el) = el {
+                    el.number(next);
+                }
+            },
+            Ast::Match { expr, arms } => {
+                expr.number(next);
+                for arm in arms {
+                    arm.body.number(next);
+                }
+            },
+            Ast::For { start, end, body, .. } => {
+                start.number(next);
+                end.number(next);
+                body.number(next);
+            },
+        }
+    }
+}
+
+/// Parameter of a function with its type if it is annotated like `(x: int)`.
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub name: String,
+    pub ty: Option<TypeExpr>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Prototype {
     pub name: String,
-    pub args: Vec<String>,
+    pub args: Vec<Param>,
+    /// Annotated return type like `: int` before the `=`.
+    pub ret: Option<TypeExpr>,
+}
+
+/// Constant a pattern compares a value with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+}
+
+/// Shape of a value that `let` or `match` takes apart, binding names to its parts.
+/// `let` only takes names and tuples.
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// `_` matches anything without binding it.
+    Wildcard,
+    /// Binds the value unless it names a constructor without fields.
+    Ident(String),
+    /// The empty tuple pattern matches unit.
+    Tuple(Vec<Pattern>),
+    Literal(Literal),
+    /// List of exactly these items `[a; b]`.
+    List(Vec<Pattern>),
+    /// `head :: tail`
+    Cons(Box<Pattern>, Box<Pattern>),
+    /// Union case with patterns for its fields `Rect w h`.
+    Constructor(String, Vec<Pattern>),
+}
+
+/// `| pattern -> body`, the span covers the whole arm.
+#[derive(Clone, Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: AstNode,
+    pub span: Span,
+}
+
+/// Type written in the source like `int * float list`.
+#[derive(Clone, Debug)]
+pub enum TypeExpr {
+    /// Builtin or declared type like `int` or `Point`.
+    Named(String),
+    /// Type constructor applied to its argument like `int list`.
+    Applied(Box<TypeExpr>, String),
+    Tuple(Vec<TypeExpr>),
+    Fn(Box<TypeExpr>, Box<TypeExpr>),
+}
+
+#[derive(Clone, Debug)]
+pub struct FieldDef {
+    pub name: String,
+    pub ty: TypeExpr,
+}
+
+/// Case of a union like `Rect of float * float`.
+#[derive(Clone, Debug)]
+pub struct CaseDef {
+    pub name: String,
+    pub fields: Vec<TypeExpr>,
+}
+
+/// Right-hand side of a `type` declaration.
+#[derive(Clone, Debug)]
+pub enum TypeDef {
+    /// `{ x: float; y: float }`
+    Record(Vec<FieldDef>),
+    /// `| Circle of float | Rect of float * float`
+    Union(Vec<CaseDef>),
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -26,39 +182,111 @@ pub enum BinOpKind {
     Add,
     Sub,
     Mul,
+    Div,
+    Mod,
     Eq,
     NotEq,
     LessThan,
     LessEq,
     GreaterThan,
     GreaterEq,
+    And,
+    Or,
+    /// Prepends an element to a list.
+    Cons,
+    /// Concatenates two lists.
+    Append,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnOpKind {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Debug)]
 pub enum Ast {
-    Number(f64),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
     Ref(String),
+    /// Tuple `(a, b)`, the empty one is unit `()`.
+    Tuple(Vec<AstNode>),
+    /// List literal `[a; b]`.
+    List(Vec<AstNode>),
     Block(Vec<AstNode>),
     Function {
         prototype: Prototype,
         body: AstNode,
     },
+    /// Anonymous function `fn params -> body`.
+    Lambda {
+        params: Vec<Param>,
+        body: AstNode,
+    },
     Call {
         name: String,
         args: Vec<AstNode>,
     },
+    /// Application of an arbitrary expression like `(make_adder 1) 2`.
+    Apply {
+        callee: AstNode,
+        args: Vec<AstNode>,
+    },
     Variable {
+        pattern: Pattern,
+        /// Annotated type like `let x: int = ...`.
+        ty: Option<TypeExpr>,
+        expr: AstNode,
+    },
+    /// Declaration `type name = def`.
+    Type {
         name: String,
+        def: TypeDef,
+    },
+    /// Record construction `{ x = 1.0; y = 2.0 }`.
+    Record {
+        fields: Vec<(String, AstNode)>,
+    },
+    /// Field access `expr.field`.
+    Field {
         expr: AstNode,
+        field: String,
+    },
+    /// Copy of a record with some of its fields replaced `{ record with x = 1.0 }`.
+    Update {
+        record: AstNode,
+        fields: Vec<(String, AstNode)>,
     },
     BinOp {
         kind: BinOpKind,
         lhs: AstNode,
         rhs: AstNode,
     },
+    Unary {
+        kind: UnOpKind,
+        expr: AstNode,
+    },
     If {
         condition: AstNode,
         then: AstNode,
         el: Option<AstNode>,
     },
+    /// `match expr with | pattern -> body`, the first matching arm is evaluated.
+    Match {
+        expr: AstNode,
+        arms: Vec<MatchArm>,
+    },
+    /// Loop over the inclusive range `start..end`.
+    For {
+        var: String,
+        start: AstNode,
+        end: AstNode,
+        body: AstNode,
+    },
+    /// Top-level item with a syntax error. Programs with errors are not checked so the later
+    /// passes never see it.
+    Error,
 }
@@ -6,7 +6,11 @@ use lexpar::parse_rules;
 use lexpar::lexer::{LexIter, Span};
 use lexpar::parser::{ParseError, UnexpectedKind};
 
-use super::lexer::Term;
+use std::iter;
+use std::mem;
+
+use super::diagnostics::{Diagnostic, codes};
+use super::lexer::{self, LexError, Term, UnknownToken};
 use super::lexer::token::Token;
 use super::lexer::token::Token::*;
 
@@ -16,24 +20,208 @@ use self::ast::*;
 
 pub struct Parser;
 
+#[derive(Debug)]
+pub enum SyntaxError {
+    Lex(LexError),
+    Unknown(UnknownToken),
+    Parse(ParseError<Term>),
+    /// A top-level item ends before it is complete, the span is right after its last token.
+    Incomplete(Span),
+}
+
+impl From<SyntaxError> for Diagnostic {
+    fn from(err: SyntaxError) -> Self {
+        match err {
+            SyntaxError::Lex(err) => {
+                Diagnostic::from(err).with_note("the rest of the item is not checked")
+            },
+            SyntaxError::Unknown(token) => token.into(),
+            SyntaxError::Parse(ParseError::Unexpected { nonterm, token: (span, token), .. }) => {
+                Diagnostic::error(format!("Unexpected {}", describe(&token)))
+                    .with_code(codes::UNEXPECTED_TOKEN)
+                    .with_label(span, "")
+                    .with_note(format!("found while parsing `{}`", nonterm.trim_start_matches('_')))
+            },
+            SyntaxError::Parse(err) => Diagnostic::error(format!("Parse error: {:?}", err)),
+            SyntaxError::Incomplete(span) => {
+                Diagnostic::error("Unexpected end of the item")
+                    .with_code(codes::UNEXPECTED_EOF)
+                    .with_label(span, "expected more after this")
+            },
+        }
+    }
+}
+
+/// How a token is called in error messages.
+fn describe(token: &Token) -> String {
+    match token {
+        BlockStart => "indentation".to_string(),
+        BlockCont => "new line".to_string(),
+        BlockEnd => "end of the indented block".to_string(),
+        Ident(name) => format!("name `{}`", name),
+        token => format!("token {:?}", token),
+    }
+}
+
 impl Parser {
-    pub fn parse(iter: LexIter<Term>) -> lexpar::parser::Result<Vec<AstNode>, Term> {
-        let iter = iter
+    /// Parses the top-level items one at a time so a syntax error only loses the item it is in.
+    /// Such items are replaced with `Ast::Error` nodes and every error found is returned, the
+    /// unknown characters of the whole file first. Items are parsed without their unknown
+    /// characters so the errors after them are reported too.
+    pub fn parse(iter: LexIter<Term>) -> (Vec<AstNode>, Vec<SyntaxError>) {
+        let tokens = iter
             .blocks()
             .filter(|x| match *x {
                 (_, Token::Whitespace(_)) => false,
                 (_, Token::Comment(_)) => false,
                 _ => true
-            });
+            })
+            .collect::<Vec<_>>();
+
+        let mut nodes = Vec::new();
+        let mut errors = lexer::unknown_tokens(&tokens)
+            .into_iter()
+            .map(SyntaxError::Unknown)
+            .collect::<Vec<_>>();
+
+        for item in top_level_items(tokens.into_iter()) {
+            let item = item
+                .into_iter()
+                .filter(|(_, token)| match token {
+                    Unknown(_) => false,
+                    _ => true,
+                })
+                .collect::<Vec<_>>();
+
+            if item.is_empty() {
+                continue;
+            }
+
+            let first = item[0].0.clone();
+            let last = item[item.len() - 1].0.clone();
+            let span = merge(first, last.clone());
+
+            // Malformed tokens take precedence over the parse errors they cause.
+            let lex_errors = item
+                .iter()
+                .filter_map(|(span, token)| match token {
+                    Invalid(message) => Some(SyntaxError::Lex(LexError {
+                        span: span.clone(),
+                        message: message.clone(),
+                    })),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            if !lex_errors.is_empty() {
+                errors.extend(lex_errors);
+                nodes.push(AstNode::new(span, Ast::Error));
+                continue;
+            }
 
-        top_level(&mut iter.into())
+            match top_level(&mut item.into_iter().into()) {
+                Ok(items) => nodes.extend(items),
+                Err(err) => {
+                    errors.push(match err {
+                        ParseError::Unexpected { .. } => SyntaxError::Parse(err),
+                        // The tokens of the item ran out.
+                        _ => SyntaxError::Incomplete(Span::new(last.hi, last.hi, last.line)),
+                    });
+                    nodes.push(AstNode::new(span, Ast::Error));
+                },
+            }
+        }
+
+        // The ids are unique among the items of this file only.
+        let mut next = 0;
+        for node in &mut nodes {
+            node.number(&mut next);
+        }
+
+        (nodes, errors)
     }
 }
 
+/// Splits the tokens into top-level items. Parsing synchronizes at the end of each of them, a
+/// new line or the end of an indented block at the top level. So a `let` starting a line at the
+/// top level starts a new item while one later on the line is left to the parser to report.
+fn top_level_items(tokens: impl Iterator<Item = Term>) -> Vec<Vec<Term>> {
+    let mut items = Vec::new();
+    let mut item = Vec::new();
+    let mut depth = 0;
+
+    for (span, token) in tokens {
+        match token {
+            BlockCont if depth == 0 => {
+                if !item.is_empty() {
+                    items.push(mem::replace(&mut item, Vec::new()));
+                }
+                continue;
+            },
+            BlockStart => depth += 1,
+            // Unbalanced blocks are left for the parser to report.
+            BlockEnd if depth > 0 => depth -= 1,
+            _ => {},
+        }
+
+        let ends_block = token == BlockEnd && depth == 0;
+        item.push((span, token));
+
+        if ends_block {
+            items.push(mem::replace(&mut item, Vec::new()));
+        }
+    }
+
+    if !item.is_empty() {
+        items.push(item);
+    }
+
+    items
+}
+
+/// What a `let` defines, told apart before its `=`.
+enum Binding {
+    Function(Prototype),
+    Pattern(Pattern, Option<TypeExpr>),
+}
+
+/// What follows the first field name in braces.
+enum RecordBody {
+    /// Value of the first field and the rest of the fields.
+    Fields(AstNode, Vec<(String, AstNode)>),
+    /// The first name is the record being updated.
+    Update(Vec<(String, AstNode)>),
+}
+
 fn merge(a: Span, b: Span) -> Span {
     a.extend(b.hi)
 }
 
+fn create_pattern(name: String) -> Pattern {
+    if name == "_" {
+        Pattern::Wildcard
+    } else {
+        Pattern::Ident(name)
+    }
+}
+
+/// `(x)` is just `x` while `()` and `(x, y)` are tuples.
+fn create_tuple(span: Span, mut items: Vec<AstNode>) -> AstNode {
+    if items.len() == 1 {
+        items.pop().unwrap()
+    } else {
+        AstNode::new(span, Ast::Tuple(items))
+    }
+}
+
+fn create_tuple_pattern(mut items: Vec<Pattern>) -> Pattern {
+    if items.len() == 1 {
+        items.pop().unwrap()
+    } else {
+        Pattern::Tuple(items)
+    }
+}
+
 fn create_binop(kind: BinOpKind, lhs: AstNode, rhs: AstNode) -> AstNode {
     let span = Span::new(lhs.span.lo, rhs.span.hi, lhs.span.line);
     AstNode::new(span, Ast::BinOp {
@@ -43,6 +231,89 @@ fn create_binop(kind: BinOpKind, lhs: AstNode, rhs: AstNode) -> AstNode {
     })
 }
 
+fn create_unary(kind: UnOpKind, span: Span, expr: AstNode) -> AstNode {
+    let span = span.extend(expr.span.hi);
+    AstNode::new(span, Ast::Unary {
+        kind,
+        expr,
+    })
+}
+
+/// Appends an argument to a call like `x |> f a` which is `f a x`.
+fn create_application(callee: AstNode, arg: AstNode) -> AstNode {
+    let span = Span::new(
+        callee.span.lo.min(arg.span.lo),
+        callee.span.hi.max(arg.span.hi),
+        callee.span.line.min(arg.span.line));
+
+    let AstNode { id, span: callee_span, expr } = callee;
+
+    let callee = match *expr {
+        Ast::Call { name, mut args } => {
+            args.push(arg);
+            return AstNode::new(span, Ast::Call { name, args });
+        },
+        Ast::Ref(name) => return AstNode::new(span, Ast::Call { name, args: vec![arg] }),
+        expr => AstNode { id, span: callee_span, expr: Box::new(expr) },
+    };
+
+    AstNode::new(span, Ast::Apply { callee, args: vec![arg] })
+}
+
+/// `expr.a.b` accesses `a` first.
+fn create_field_access(expr: AstNode, fields: Vec<(Span, String)>) -> AstNode {
+    fields.into_iter().fold(expr, |expr, (span, field)| {
+        let span = Span::new(expr.span.lo, span.hi, expr.span.line);
+        AstNode::new(span, Ast::Field { expr, field })
+    })
+}
+
+/// `f >> g` is `fn x -> g (f x)` where `f` and `g` are evaluated once, when composed.
+fn create_composition(first: AstNode, second: AstNode) -> AstNode {
+    // `$` cannot appear in identifiers so the names do not shadow anything.
+    const PARAM: &str = "$x";
+
+    let span = Span::new(first.span.lo, second.span.hi, first.span.line);
+
+    let mut bindings = Vec::new();
+    let first = bind_operand(&mut bindings, "$f", first);
+    let second = bind_operand(&mut bindings, "$g", second);
+
+    let arg = AstNode::new(first.span.clone(), Ast::Ref(PARAM.to_string()));
+    let body = create_application(second, create_application(first, arg));
+
+    let lambda = AstNode::new(span.clone(), Ast::Lambda {
+        params: vec![Param { name: PARAM.to_string(), ty: None }],
+        body,
+    });
+
+    if bindings.is_empty() {
+        lambda
+    } else {
+        bindings.push(lambda);
+        AstNode::new(span, Ast::Block(bindings))
+    }
+}
+
+/// Binds an operand of `>>` to `name` so the function closes over its value. Names and anonymous
+/// functions are left as they are since evaluating them again has no effect.
+fn bind_operand(bindings: &mut Vec<AstNode>, name: &str, operand: AstNode) -> AstNode {
+    match &*operand.expr {
+        Ast::Ref(_) | Ast::Lambda { .. } => operand,
+        _ => {
+            let span = operand.span.clone();
+
+            bindings.push(AstNode::new(span.clone(), Ast::Variable {
+                pattern: Pattern::Ident(name.to_string()),
+                ty: None,
+                expr: operand,
+            }));
+
+            AstNode::new(span, Ast::Ref(name.to_string()))
+        },
+    }
+}
+
 parse_rules! {
     term: Term;
 
@@ -75,6 +346,7 @@ parse_rules! {
 
     __top_level: Option<AstNode> => {
         [def: def] => Some(def),
+        [ty: type_def] => Some(ty),
         [expr: expr] => Some(expr),
         [(_, BlockCont)] => None
     },
@@ -86,38 +358,76 @@ parse_rules! {
 
     // Statements
     def: AstNode => {
-        [(span, KwLet), (_, Ident(name)), params: params, (_, Assign), ex: expr] => {
+        [(span, KwLet), binding: binding, (_, Assign), ex: expr] => {
             let span = span.extend(ex.span.hi);
 
-            if params.is_empty() {
-                AstNode::new(span, Ast::Variable {
-                    name,
-                    expr: ex,
-                })
-            } else {
-                AstNode::new(span, Ast::Function {
-                    prototype: Prototype {
-                        name,
-                        args: params,
-                    },
+            match binding {
+                Binding::Function(prototype) => AstNode::new(span, Ast::Function {
+                    prototype,
                     body: ex,
-                })
+                }),
+                Binding::Pattern(pattern, ty) => AstNode::new(span, Ast::Variable {
+                    pattern,
+                    ty,
+                    expr: ex,
+                }),
             }
         }
     },
 
-    // T0 expr (Binary operations with a precedence algorithm)
+    // Function with its parameters or a value taken apart by a pattern
+    binding: Binding => {
+        [(_, Ident(name)), params: params, ret: annotation] => {
+            if params.is_empty() {
+                Binding::Pattern(create_pattern(name), ret)
+            } else {
+                Binding::Function(Prototype { name, args: params, ret })
+            }
+        },
+        [pattern: tuple_pattern, ty: annotation] => Binding::Pattern(pattern, ty),
+    },
+
+    // T0 expr (Pipelines and compositions bind the loosest)
     #[binop(infix)]
-    expr: AstNode => _expr where u32 => |lhs, rhs| {
-        &(_, Eq)            | 0 => create_binop(BinOpKind::Eq, lhs, rhs),
-        &(_, NotEq)         | 0 => create_binop(BinOpKind::NotEq, lhs, rhs),
-        &(_, GreaterThan)   | 0 => create_binop(BinOpKind::GreaterThan, lhs, rhs),
-        &(_, GreaterEq)     | 0 => create_binop(BinOpKind::GreaterEq, lhs, rhs),
-        &(_, LessThan)      | 0 => create_binop(BinOpKind::LessThan, lhs, rhs),
-        &(_, LessEq)        | 0 => create_binop(BinOpKind::LessEq, lhs, rhs),
-        &(_, Plus)          | 1 => create_binop(BinOpKind::Add, lhs, rhs),
-        &(_, Minus)         | 1 => create_binop(BinOpKind::Sub, lhs, rhs),
-        &(_, Asterisk)      | 2 => create_binop(BinOpKind::Mul, lhs, rhs),
+    expr: AstNode => cons where u32 => |lhs, rhs| {
+        &(_, Pipeline)      | 0 => create_application(rhs, lhs),
+        &(_, Compose)       | 1 => create_composition(lhs, rhs),
+    },
+
+    // Cons is right associative so it is left out of the precedence algorithm and binds looser than
+    // the rest of the operations
+    cons: AstNode => {
+        [head: operation, tail: _cons] => {
+            if let Some(tail) = tail {
+                create_binop(BinOpKind::Cons, head, tail)
+            } else {
+                head
+            }
+        },
+    },
+
+    _cons: Option<AstNode> => {
+        [(_, Cons), tail: cons] => Some(tail),
+        [@] => None,
+    },
+
+    // Binary operations with a precedence algorithm
+    #[binop(infix)]
+    operation: AstNode => _expr where u32 => |lhs, rhs| {
+        &(_, KwOr)          | 0 => create_binop(BinOpKind::Or, lhs, rhs),
+        &(_, KwAnd)         | 1 => create_binop(BinOpKind::And, lhs, rhs),
+        &(_, Eq)            | 2 => create_binop(BinOpKind::Eq, lhs, rhs),
+        &(_, NotEq)         | 2 => create_binop(BinOpKind::NotEq, lhs, rhs),
+        &(_, GreaterThan)   | 2 => create_binop(BinOpKind::GreaterThan, lhs, rhs),
+        &(_, GreaterEq)     | 2 => create_binop(BinOpKind::GreaterEq, lhs, rhs),
+        &(_, LessThan)      | 2 => create_binop(BinOpKind::LessThan, lhs, rhs),
+        &(_, LessEq)        | 2 => create_binop(BinOpKind::LessEq, lhs, rhs),
+        &(_, Append)        | 3 => create_binop(BinOpKind::Append, lhs, rhs),
+        &(_, Plus)          | 4 => create_binop(BinOpKind::Add, lhs, rhs),
+        &(_, Minus)         | 4 => create_binop(BinOpKind::Sub, lhs, rhs),
+        &(_, Asterisk)      | 5 => create_binop(BinOpKind::Mul, lhs, rhs),
+        &(_, FSlash)        | 5 => create_binop(BinOpKind::Div, lhs, rhs),
+        &(_, Percent)       | 5 => create_binop(BinOpKind::Mod, lhs, rhs),
     },
 
     // T1 expr (Compound expressions)
@@ -128,34 +438,477 @@ parse_rules! {
             AstNode::new(merge(l, r), Ast::Block(top))
         },
 
-        // Reference or function call
-        [(span, Ident(name)), args: args] => {
-            if let Some((call_span, args)) = args {
+        // Reference or function call, or the application of a field
+        [(span, Ident(name)), fields: fields, args: args] => {
+            if !fields.is_empty() {
+                let callee = create_field_access(AstNode::new(span, Ast::Ref(name)), fields);
+
+                if let Some((call_span, args)) = args {
+                    AstNode::new(merge(callee.span.clone(), call_span), Ast::Apply { callee, args })
+                } else {
+                    callee
+                }
+            } else if let Some((call_span, args)) = args {
                 AstNode::new(merge(span, call_span), Ast::Call { name, args })
             } else {
                 AstNode::new(span, Ast::Ref(name))
             }
         },
 
+        // Unary operations bind tighter than any binary operation
+        [(span, Minus), ex: _expr] => create_unary(UnOpKind::Neg, span, ex),
+        [(span, Excl), ex: _expr] => create_unary(UnOpKind::Not, span, ex),
+
+        // Anonymous function
+        [(span, KwFn), params: params, (_, Arrow), body: expr] => {
+            let span = span.extend(body.span.hi);
+
+            AstNode::new(span, Ast::Lambda {
+                params,
+                body,
+            })
+        },
+
         [_if: _if] => _if,
-        [ex: __expr] => ex,
+        [_for: _for] => _for,
+        [_match: _match] => _match,
+
+        // Simple expression or its application
+        [callee: __expr, args: args] => {
+            if let Some((call_span, args)) = args {
+                AstNode::new(merge(callee.span.clone(), call_span), Ast::Apply { callee, args })
+            } else {
+                callee
+            }
+        },
     },
 
-    // T2 expr (Simple expressions)
+    // T2 expr (Simple expressions and their fields)
     __expr: AstNode => {
+        [ex: ___expr, fields: fields] => create_field_access(ex, fields),
+    },
+
+    // T3 expr (Atoms)
+    ___expr: AstNode => {
         // Reference (Function call argument)
         [(span, Ident(name))] => AstNode::new(span, Ast::Ref(name)),
 
-        // Parenthesis expression
-        [(_, LParen), ex: expr, (_, RParen)] => ex,
+        // Parenthesis expression, tuple or unit
+        [(l, LParen), items: tuple, (r, RParen)] => create_tuple(merge(l, r), items),
+
+        // List literal
+        [(l, LBracket), items: list, (r, RBracket)] => AstNode::new(merge(l, r), Ast::List(items)),
+
+        // Record construction or copy and update
+        [(l, LBrace), record: record, (r, RBrace)] => AstNode::new(merge(l, r), record),
 
         // Literal expression
         [literal: literal] => literal,
     },
 
     literal: AstNode => {
-        // Number literal
-        [(span, Number(num))] => AstNode::new(span, Ast::Number(num)),
+        // Number literals
+        [(span, Integer(value))] => AstNode::new(span, Ast::Integer(value)),
+        [(span, Float(value))] => AstNode::new(span, Ast::Float(value)),
+
+        // Boolean literals
+        [(span, KwTrue)] => AstNode::new(span, Ast::Bool(true)),
+        [(span, KwFalse)] => AstNode::new(span, Ast::Bool(false)),
+
+        // Character literal, validated by the lexer
+        [(span, SingleQuote(value))] => AstNode::new(span, Ast::Char(value)),
+
+        // String literal, escape sequences are already replaced by the lexer
+        [(span, DoubleQuote(value))] => AstNode::new(span, Ast::Str(value)),
+    },
+}
+
+// Tuple helpers
+parse_rules! {
+    term: Term;
+
+    // Comma separated expressions
+    tuple: Vec<AstNode> => {
+        [first: expr, rest: _tuple] => iter::once(first).chain(rest).collect(),
+        [@] => Vec::new(),
+    },
+
+    #[fold(items)]
+    _tuple: Vec<AstNode> => {
+        [(_, Comma), ex: expr] => {
+            items.push(ex);
+            items
+        },
+        [@] => Vec::new()
+    },
+}
+
+// List helpers
+parse_rules! {
+    term: Term;
+
+    // Semicolon separated expressions
+    list: Vec<AstNode> => {
+        [first: expr, rest: _list] => iter::once(first).chain(rest).collect(),
+        [@] => Vec::new(),
+    },
+
+    #[fold(items)]
+    _list: Vec<AstNode> => {
+        [(_, Semicolon), ex: expr] => {
+            items.push(ex);
+            items
+        },
+        [@] => Vec::new()
+    },
+}
+
+// Record helpers
+parse_rules! {
+    term: Term;
+
+    // Field accesses
+    #[fold(fields)]
+    fields: Vec<(Span, String)> => {
+        [(_, Dot), (span, Ident(name))] => {
+            fields.push((span, name));
+            fields
+        },
+        [@] => Vec::new()
+    },
+
+    // Both forms start with a name so the rest decides which one it is
+    record: Ast => {
+        [(span, Ident(name)), body: _record] => match body {
+            RecordBody::Fields(value, fields) => Ast::Record {
+                fields: iter::once((name, value)).chain(fields).collect(),
+            },
+            RecordBody::Update(fields) => Ast::Update {
+                record: AstNode::new(span, Ast::Ref(name)),
+                fields,
+            },
+        },
+    },
+
+    _record: RecordBody => {
+        [(_, Assign), value: expr, fields: _field_values] => RecordBody::Fields(value, fields),
+        [(_, KwWith), fields: field_values] => RecordBody::Update(fields),
+    },
+
+    // Semicolon separated `name = value`
+    field_values: Vec<(String, AstNode)> => {
+        [(_, Ident(name)), (_, Assign), value: expr, rest: _field_values] => {
+            iter::once((name, value)).chain(rest).collect()
+        },
+    },
+
+    #[fold(fields)]
+    _field_values: Vec<(String, AstNode)> => {
+        [(_, Semicolon), (_, Ident(name)), (_, Assign), value: expr] => {
+            fields.push((name, value));
+            fields
+        },
+        [@] => Vec::new()
+    },
+}
+
+// Type declarations
+parse_rules! {
+    term: Term;
+
+    // The span covers the name, the definition is reported as a whole
+    type_def: AstNode => {
+        [(span, KwType), (name_span, Ident(name)), (_, Assign), def: type_body] => {
+            AstNode::new(merge(span, name_span), Ast::Type { name, def })
+        },
+    },
+
+    type_body: TypeDef => {
+        [(_, LBrace), fields: field_defs, (_, RBrace)] => TypeDef::Record(fields),
+        [cases: cases] => TypeDef::Union(cases),
+
+        // Definition on the following lines
+        [(_, BlockStart), def: _type_body, (_, BlockEnd)] => def,
+    },
+
+    _type_body: TypeDef => {
+        [(_, LBrace), fields: field_defs, (_, RBrace)] => TypeDef::Record(fields),
+        [cases: cases] => TypeDef::Union(cases),
+    },
+
+    // Semicolon separated `name: type`
+    field_defs: Vec<FieldDef> => {
+        [first: field_def, rest: _field_defs] => iter::once(first).chain(rest).collect(),
+    },
+
+    #[fold(fields)]
+    _field_defs: Vec<FieldDef> => {
+        [(_, Semicolon), field: field_def] => {
+            fields.push(field);
+            fields
+        },
+        [@] => Vec::new()
+    },
+
+    field_def: FieldDef => {
+        [(_, Ident(name)), (_, Colon), ty: type_expr] => FieldDef { name, ty },
+    },
+}
+
+// Union helpers
+parse_rules! {
+    term: Term;
+
+    // Pipe separated cases, lines starting with a pipe continue the previous one
+    cases: Vec<CaseDef> => {
+        [first: first_case, rest: _cases] => iter::once(first).chain(rest).collect(),
+    },
+
+    #[fold(cases)]
+    _cases: Vec<CaseDef> => {
+        [(_, Pipe), case: case] => {
+            cases.push(case);
+            cases
+        },
+        [@] => Vec::new()
+    },
+
+    // The pipe before the first case is optional
+    first_case: CaseDef => {
+        [(_, Pipe), case: case] => case,
+        [case: case] => case,
+    },
+
+    case: CaseDef => {
+        [(_, Ident(name)), fields: case_fields] => CaseDef { name, fields },
+    },
+
+    case_fields: Vec<TypeExpr> => {
+        [(_, KwOf), first: __type_expr, rest: _type_items] => iter::once(first).chain(rest).collect(),
+        [@] => Vec::new(),
+    },
+}
+
+// Match helpers
+parse_rules! {
+    term: Term;
+
+    _match: AstNode => {
+        [(span, KwMatch), ex: expr, (_, KwWith), arms: arms] => {
+            let hi = arms.last().map_or(span.hi, |arm| arm.span.hi);
+            AstNode::new(span.extend(hi), Ast::Match { expr: ex, arms })
+        },
+    },
+
+    // Arms after `with` or on the following lines
+    arms: Vec<MatchArm> => {
+        [first: first_arm, rest: _arms] => iter::once(first).chain(rest).collect(),
+        [(_, BlockStart), first: first_arm, rest: _arms, (_, BlockEnd)] => {
+            iter::once(first).chain(rest).collect()
+        },
+    },
+
+    #[fold(arms)]
+    _arms: Vec<MatchArm> => {
+        [(_, Pipe), arm: arm] => {
+            arms.push(arm);
+            arms
+        },
+        [@] => Vec::new()
+    },
+
+    // The pipe before the first arm is optional
+    first_arm: MatchArm => {
+        [(_, Pipe), arm: arm] => arm,
+        [arm: arm] => arm,
+    },
+
+    arm: MatchArm => {
+        [pattern: match_pattern, (_, Arrow), body: expr] => {
+            let (span, pattern) = pattern;
+            MatchArm { span: merge(span, body.span.clone()), pattern, body }
+        },
+    },
+}
+
+// Patterns of `match` with the span of their first token
+parse_rules! {
+    term: Term;
+
+    // `::` is right associative
+    match_pattern: (Span, Pattern) => {
+        [head: cons_pattern, tail: _match_pattern] => match tail {
+            Some((_, tail)) => (head.0, Pattern::Cons(Box::new(head.1), Box::new(tail))),
+            None => head,
+        },
+    },
+
+    _match_pattern: Option<(Span, Pattern)> => {
+        [(_, Cons), tail: match_pattern] => Some(tail),
+        [@] => None,
+    },
+
+    // Constructor applied to patterns of its fields or a single pattern
+    cons_pattern: (Span, Pattern) => {
+        [(span, Ident(name)), args: pattern_args] => if args.is_empty() {
+            (span, create_pattern(name))
+        } else {
+            (span, Pattern::Constructor(name, args))
+        },
+        [atom: atom_pattern] => atom,
+    },
+
+    #[fold(args)]
+    pattern_args: Vec<Pattern> => {
+        [(_, Ident(name))] => {
+            args.push(create_pattern(name));
+            args
+        },
+        [atom: atom_pattern] => {
+            args.push(atom.1);
+            args
+        },
+        [@] => Vec::new()
+    },
+
+    atom_pattern: (Span, Pattern) => {
+        [(span, LParen), items: match_patterns, (_, RParen)] => (span, create_tuple_pattern(items)),
+        [(span, LBracket), items: list_patterns, (_, RBracket)] => (span, Pattern::List(items)),
+
+        [(span, Integer(value))] => (span, Pattern::Literal(Literal::Integer(value))),
+        [(span, Float(value))] => (span, Pattern::Literal(Literal::Float(value))),
+        [(span, Minus), (r, Integer(value))] => {
+            (span.extend(r.hi), Pattern::Literal(Literal::Integer(-value)))
+        },
+        [(span, Minus), (r, Float(value))] => {
+            (span.extend(r.hi), Pattern::Literal(Literal::Float(-value)))
+        },
+        [(span, KwTrue)] => (span, Pattern::Literal(Literal::Bool(true))),
+        [(span, KwFalse)] => (span, Pattern::Literal(Literal::Bool(false))),
+        [(span, SingleQuote(value))] => (span, Pattern::Literal(Literal::Char(value))),
+        [(span, DoubleQuote(value))] => (span, Pattern::Literal(Literal::Str(value))),
+    },
+
+    // Comma separated patterns
+    match_patterns: Vec<Pattern> => {
+        [first: match_pattern, rest: _match_patterns] => iter::once(first.1).chain(rest).collect(),
+        [@] => Vec::new(),
+    },
+
+    #[fold(items)]
+    _match_patterns: Vec<Pattern> => {
+        [(_, Comma), pattern: match_pattern] => {
+            items.push(pattern.1);
+            items
+        },
+        [@] => Vec::new()
+    },
+
+    // Semicolon separated patterns
+    list_patterns: Vec<Pattern> => {
+        [first: match_pattern, rest: _list_patterns] => iter::once(first.1).chain(rest).collect(),
+        [@] => Vec::new(),
+    },
+
+    #[fold(items)]
+    _list_patterns: Vec<Pattern> => {
+        [(_, Semicolon), pattern: match_pattern] => {
+            items.push(pattern.1);
+            items
+        },
+        [@] => Vec::new()
+    },
+}
+
+// Type expressions
+parse_rules! {
+    term: Term;
+
+    // Function types are right associative
+    type_expr: TypeExpr => {
+        [arg: _type_expr, ret: _type_ret] => {
+            if let Some(ret) = ret {
+                TypeExpr::Fn(Box::new(arg), Box::new(ret))
+            } else {
+                arg
+            }
+        },
+    },
+
+    _type_ret: Option<TypeExpr> => {
+        [(_, Arrow), ret: type_expr] => Some(ret),
+        [@] => None,
+    },
+
+    // Tuple types bind tighter than function types
+    _type_expr: TypeExpr => {
+        [first: __type_expr, rest: _type_items] => {
+            if rest.is_empty() {
+                first
+            } else {
+                TypeExpr::Tuple(iter::once(first).chain(rest).collect())
+            }
+        },
+    },
+
+    #[fold(items)]
+    _type_items: Vec<TypeExpr> => {
+        [(_, Asterisk), item: __type_expr] => {
+            items.push(item);
+            items
+        },
+        [@] => Vec::new()
+    },
+
+    // Type constructors follow their argument like `int list list`
+    __type_expr: TypeExpr => {
+        [ty: ___type_expr, names: _type_constructors] => {
+            names.into_iter().fold(ty, |ty, name| TypeExpr::Applied(Box::new(ty), name))
+        },
+    },
+
+    #[fold(names)]
+    _type_constructors: Vec<String> => {
+        [(_, Ident(name))] => {
+            names.push(name);
+            names
+        },
+        [@] => Vec::new()
+    },
+
+    ___type_expr: TypeExpr => {
+        [(_, Ident(name))] => TypeExpr::Named(name),
+        [(_, LParen), ty: type_expr, (_, RParen)] => ty,
+    },
+}
+
+// Patterns
+parse_rules! {
+    term: Term;
+
+    pattern: Pattern => {
+        [(_, Ident(name))] => create_pattern(name),
+        [pattern: tuple_pattern] => pattern,
+    },
+
+    // Parenthesized pattern, tuple pattern or unit
+    tuple_pattern: Pattern => {
+        [(_, LParen), items: patterns, (_, RParen)] => create_tuple_pattern(items),
+    },
+
+    // Comma separated patterns
+    patterns: Vec<Pattern> => {
+        [first: pattern, rest: _patterns] => iter::once(first).chain(rest).collect(),
+        [@] => Vec::new(),
+    },
+
+    #[fold(patterns)]
+    _patterns: Vec<Pattern> => {
+        [(_, Comma), pattern: pattern] => {
+            patterns.push(pattern);
+            patterns
+        },
+        [@] => Vec::new()
     },
 }
 
@@ -185,6 +938,28 @@ parse_rules! {
     },
 }
 
+// For loops
+parse_rules! {
+    term: Term;
+
+    _for: AstNode => {
+        [
+            (span, KwFor), (_, Ident(var)), (_, KwIn),
+            start: expr, (_, Range), end: expr,
+            (_, KwDo), body: expr
+        ] => {
+            let span = span.extend(body.span.hi);
+
+            AstNode::new(span, Ast::For {
+                var,
+                start,
+                end,
+                body,
+            })
+        },
+    },
+}
+
 // Reference or function invocation helpers
 parse_rules! {
     term: Term;
@@ -212,13 +987,23 @@ parse_rules! {
 parse_rules! {
     term: Term;
 
-    // Function parameters
+    // Function parameters, optionally annotated
     #[fold(params)]
-    params: Vec<String> => {
+    params: Vec<Param> => {
         [(_, Ident(name))] => {
-            params.push(name);
+            params.push(Param { name, ty: None });
+            params
+        },
+        [(_, LParen), (_, Ident(name)), (_, Colon), ty: type_expr, (_, RParen)] => {
+            params.push(Param { name, ty: Some(ty) });
             params
         },
         [@] => Vec::new()
     },
+
+    // Type of a binding after a colon
+    annotation: Option<TypeExpr> => {
+        [(_, Colon), ty: type_expr] => Some(ty),
+        [@] => None,
+    },
 }
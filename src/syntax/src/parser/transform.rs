@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::iter::Peekable;
 
 use lexpar::lexer::{LexIter, Span};
 
@@ -37,7 +38,7 @@ impl BlockIterator for LexIter<Term> {
 }
 
 pub struct BlockIter {
-    iter: LexIter<Term>,
+    iter: Peekable<LexIter<Term>>,
     blocks: Vec<Depth>,
     closing: u32,
     last_span: Option<Span>
@@ -46,7 +47,7 @@ pub struct BlockIter {
 impl BlockIter {
     pub fn new(iter: LexIter<Term>) -> Self {
         Self {
-            iter,
+            iter: iter.peekable(),
             blocks: vec![0],
             closing: 0,
             last_span: None,
@@ -67,8 +68,23 @@ impl Iterator for BlockIter {
                 None
             }
         } else {
-            self.iter.next().map(|(span, token)| {
+            let term = self.iter.next();
+            // Lines starting with `|` continue the previous one like the cases of a union or
+            // the arms of a match. Lines starting with `|>` continue a pipeline and can be
+            // indented further.
+            let (pipe, pipeline) = match self.iter.peek() {
+                Some((_, Token::Pipe)) => (true, false),
+                Some((_, Token::Pipeline)) => (false, true),
+                _ => (false, false),
+            };
+
+            term.map(|(span, token)| {
                 let tok = if let Some(depth) = token.parse_indent() {
+                    let last = *self.blocks.last().unwrap();
+                    if (pipe && depth == last) || (pipeline && depth >= last) {
+                        return (span, token);
+                    }
+
                     if depth == 0 {
                         if let Token::Whitespace(space) = token {
                             if space == "\n" {
@@ -76,7 +92,6 @@ impl Iterator for BlockIter {
                             }
                         }
                     }
-                    let last = self.blocks.last().unwrap().clone();
                     match depth.cmp(&last) {
                         Ordering::Equal => Token::BlockCont,
                         Ordering::Greater => {
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn annotated_parameters() {
+    assert_eq!(item("let f (x: int) = x\n"), "(let f [(x: int)] x)");
+    assert_eq!(item("let f (x: int) y (z: float) = x\n"), "(let f [(x: int) y (z: float)] x)");
+}
+
+#[test]
+fn return_type() {
+    assert_eq!(item("let f x: int = x\n"), "(let f [x] : int x)");
+    assert_eq!(item("let f (x: int): bool = x > 0\n"), "(let f [(x: int)] : bool (> x 0))");
+}
+
+#[test]
+fn annotated_bindings() {
+    assert_eq!(item("let x: float = 1.0\n"), "(let x: float 1.0)");
+    assert_eq!(item("let (a, b): int * int = t\n"), "(let (a, b): (int * int) t)");
+}
+
+#[test]
+fn annotated_lambda_parameters() {
+    assert_eq!(expr("fn (x: int) y -> x"), "(fn [(x: int) y] x)");
+}
+
+#[test]
+fn type_expressions() {
+    let annotation = |ty: &str| item(&format!("let x: {} = y\n", ty));
+
+    assert_eq!(annotation("int list list"), "(let x: ((int list) list) y)");
+    assert_eq!(annotation("int * string list"), "(let x: (int * (string list)) y)");
+    assert_eq!(annotation("int -> int -> bool"), "(let x: (int -> (int -> bool)) y)");
+    assert_eq!(annotation("(int -> int) -> int"), "(let x: ((int -> int) -> int) y)");
+    assert_eq!(annotation("(int * int) list"), "(let x: ((int * int) list) y)");
+}
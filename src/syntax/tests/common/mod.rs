@@ -0,0 +1,188 @@
+//! Parses sources and prints the trees as s-expressions so tests can compare them as strings.
+
+#![allow(dead_code)]
+
+use syntax::lexer;
+use syntax::parser::{Parser, SyntaxError};
+use syntax::parser::ast::*;
+
+pub fn parse_with_errors(source: &str) -> (Vec<AstNode>, Vec<SyntaxError>) {
+    Parser::parse(lexer::lexer().src_iter(source))
+}
+
+pub fn parse(source: &str) -> Vec<AstNode> {
+    let (nodes, errors) = parse_with_errors(source);
+    assert!(errors.is_empty(), "Syntax errors {:?}", errors);
+    nodes
+}
+
+/// The only item of the source.
+pub fn item(source: &str) -> String {
+    let nodes = parse(source);
+    assert_eq!(nodes.len(), 1, "Expected a single item in {:?}", source);
+    show(&nodes[0])
+}
+
+/// The expression bound by `let x = ...`.
+pub fn expr(source: &str) -> String {
+    let nodes = parse(&format!("let x = {}\n", source));
+
+    match &*nodes[0].expr {
+        Ast::Variable { expr, .. } => show(expr),
+        ast => panic!("Expected a variable, found {:?}", ast),
+    }
+}
+
+pub fn show(node: &AstNode) -> String {
+    match &*node.expr {
+        Ast::Integer(value) => value.to_string(),
+        Ast::Float(value) => format!("{:?}", value),
+        Ast::Bool(value) => value.to_string(),
+        Ast::Char(value) => format!("{:?}", value),
+        Ast::Str(value) => format!("{:?}", value),
+        Ast::Ref(name) => name.clone(),
+        Ast::Tuple(items) => format!("(tuple{})", all(items)),
+        Ast::List(items) => format!("[{}]", show_all(items).join(" ")),
+        Ast::Block(exprs) => format!("(block{})", all(exprs)),
+        Ast::Function { prototype, body } => {
+            let ret = match &prototype.ret {
+                Some(ty) => format!(": {} ", show_type(ty)),
+                None => String::new(),
+            };
+            format!("(let {} [{}] {}{})", prototype.name, show_params(&prototype.args), ret, show(body))
+        },
+        Ast::Lambda { params, body } => format!("(fn [{}] {})", show_params(params), show(body)),
+        Ast::Call { name, args } => format!("({}{})", name, all(args)),
+        Ast::Apply { callee, args } => format!("(apply {}{})", show(callee), all(args)),
+        Ast::Variable { pattern, ty: Some(ty), expr } => {
+            format!("(let {}: {} {})", show_pattern(pattern), show_type(ty), show(expr))
+        },
+        Ast::Variable { pattern, ty: None, expr } => {
+            format!("(let {} {})", show_pattern(pattern), show(expr))
+        },
+        Ast::Type { name, def: TypeDef::Record(fields) } => {
+            let fields = fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name, show_type(&field.ty)))
+                .collect::<Vec<_>>();
+            format!("(type {} {{{}}})", name, fields.join("; "))
+        },
+        Ast::Type { name, def: TypeDef::Union(cases) } => {
+            let cases = cases
+                .iter()
+                .map(|case| {
+                    let fields = case.fields.iter().map(show_type).collect::<Vec<_>>();
+                    format!(" | {}{}", case.name, if fields.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" of {}", fields.join(" * "))
+                    })
+                })
+                .collect::<String>();
+            format!("(type {}{})", name, cases)
+        },
+        Ast::Record { fields } => format!("{{{}}}", show_fields(fields)),
+        Ast::Field { expr, field } => format!("(. {} {})", show(expr), field),
+        Ast::Update { record, fields } => format!("{{{} with {}}}", show(record), show_fields(fields)),
+        Ast::BinOp { kind, lhs, rhs } => format!("({} {} {})", operator(*kind), show(lhs), show(rhs)),
+        Ast::Unary { kind: UnOpKind::Neg, expr } => format!("(- {})", show(expr)),
+        Ast::Unary { kind: UnOpKind::Not, expr } => format!("(! {})", show(expr)),
+        Ast::If { condition, then, el: Some(el) } => {
+            format!("(if {} {} {})", show(condition), show(then), show(el))
+        },
+        Ast::If { condition, then, el: None } => format!("(if {} {})", show(condition), show(then)),
+        Ast::Match { expr, arms } => {
+            let arms = arms
+                .iter()
+                .map(|arm| format!(" ({} -> {})", show_pattern(&arm.pattern), show(&arm.body)))
+                .collect::<String>();
+            format!("(match {}{})", show(expr), arms)
+        },
+        Ast::For { var, start, end, body } => {
+            format!("(for {} {} {} {})", var, show(start), show(end), show(body))
+        },
+        Ast::Error => "error".to_string(),
+    }
+}
+
+fn show_all(nodes: &[AstNode]) -> Vec<String> {
+    nodes.iter().map(show).collect()
+}
+
+/// Each node preceded by a space.
+fn all(nodes: &[AstNode]) -> String {
+    show_all(nodes).iter().map(|node| format!(" {}", node)).collect()
+}
+
+fn show_params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| match &param.ty {
+            Some(ty) => format!("({}: {})", param.name, show_type(ty)),
+            None => param.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn show_fields(fields: &[(String, AstNode)]) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, show(value)))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub fn show_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Ident(name) => name.clone(),
+        Pattern::Tuple(items) => {
+            format!("({})", items.iter().map(show_pattern).collect::<Vec<_>>().join(", "))
+        },
+        Pattern::Literal(Literal::Integer(value)) => value.to_string(),
+        Pattern::Literal(Literal::Float(value)) => format!("{:?}", value),
+        Pattern::Literal(Literal::Bool(value)) => value.to_string(),
+        Pattern::Literal(Literal::Char(value)) => format!("{:?}", value),
+        Pattern::Literal(Literal::Str(value)) => format!("{:?}", value),
+        Pattern::List(items) => {
+            format!("[{}]", items.iter().map(show_pattern).collect::<Vec<_>>().join("; "))
+        },
+        Pattern::Cons(head, tail) => format!("(:: {} {})", show_pattern(head), show_pattern(tail)),
+        Pattern::Constructor(name, args) => {
+            let args = args.iter().map(|arg| format!(" {}", show_pattern(arg))).collect::<String>();
+            format!("({}{})", name, args)
+        },
+    }
+}
+
+pub fn show_type(ty: &TypeExpr) -> String {
+    match ty {
+        TypeExpr::Named(name) => name.clone(),
+        TypeExpr::Applied(ty, name) => format!("({} {})", show_type(ty), name),
+        TypeExpr::Tuple(items) => {
+            format!("({})", items.iter().map(show_type).collect::<Vec<_>>().join(" * "))
+        },
+        TypeExpr::Fn(arg, ret) => format!("({} -> {})", show_type(arg), show_type(ret)),
+    }
+}
+
+fn operator(kind: BinOpKind) -> &'static str {
+    match kind {
+        BinOpKind::Add => "+",
+        BinOpKind::Sub => "-",
+        BinOpKind::Mul => "*",
+        BinOpKind::Div => "/",
+        BinOpKind::Mod => "%",
+        BinOpKind::Eq => "==",
+        BinOpKind::NotEq => "!=",
+        BinOpKind::LessThan => "<",
+        BinOpKind::LessEq => "<=",
+        BinOpKind::GreaterThan => ">",
+        BinOpKind::GreaterEq => ">=",
+        BinOpKind::And => "and",
+        BinOpKind::Or => "or",
+        BinOpKind::Cons => "::",
+        BinOpKind::Append => "@",
+    }
+}
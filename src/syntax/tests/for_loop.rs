@@ -0,0 +1,42 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn for_loops() {
+    assert_eq!(expr("for i in 1..10 do f i"), "(for i 1 10 (f i))");
+    assert_eq!(expr("for _ in 1..n do f ()"), "(for _ 1 n (f (tuple)))");
+}
+
+#[test]
+fn range_bounds_are_expressions() {
+    assert_eq!(expr("for i in a + 1..n * 2 do f i"), "(for i (+ a 1) (* n 2) (f i))");
+    assert_eq!(expr("for i in n-1..n do f i"), "(for i (- n 1) n (f i))");
+    assert_eq!(expr("for i in len xs..0 do f i"), "(for i (len xs) 0 (f i))");
+}
+
+#[test]
+fn bodies_are_blocks() {
+    let source = "\
+let f n =
+    for i in 1..n do
+        let x = i * 2
+        print_int x
+";
+    assert_eq!(
+        item(source),
+        "(let f [n] (block (for i 1 n (block (let x (* i 2)) (print_int x)))))");
+}
+
+#[test]
+fn statements_follow_loops() {
+    let source = "\
+let f n =
+    for i in 1..n do
+        print_int i
+    print_int n
+";
+    assert_eq!(
+        item(source),
+        "(let f [n] (block (for i 1 n (block (print_int i))) (print_int n)))");
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn lambda() {
+    assert_eq!(expr("fn x -> x + 1"), "(fn [x] (+ x 1))");
+    assert_eq!(expr("fn a b -> a * b"), "(fn [a b] (* a b))");
+    assert_eq!(expr("fn _ -> 0"), "(fn [_] 0)");
+}
+
+#[test]
+fn lambda_body_extends_to_the_end() {
+    assert_eq!(expr("fn x -> fn y -> x + y"), "(fn [x] (fn [y] (+ x y)))");
+    assert_eq!(expr("fn x -> if x then 1 else 2"), "(fn [x] (if x 1 2))");
+}
+
+#[test]
+fn lambda_without_parameters_is_left_for_the_checker() {
+    assert_eq!(expr("fn -> 1"), "(fn [] 1)");
+}
+
+#[test]
+fn lambda_as_an_argument() {
+    assert_eq!(expr("map (fn x -> x * 2) xs"), "(map (fn [x] (* x 2)) xs)");
+}
+
+#[test]
+fn lambda_applied_directly() {
+    assert_eq!(expr("(fn x -> x) 1"), "(apply (fn [x] x) 1)");
+}
+
+#[test]
+fn lambda_returned_from_a_function() {
+    assert_eq!(item("let make_adder n = fn x -> x + n\n"), "(let make_adder [n] (fn [x] (+ x n)))");
+}
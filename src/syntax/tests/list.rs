@@ -0,0 +1,40 @@
+mod common;
+
+use common::expr;
+
+#[test]
+fn list_literal() {
+    assert_eq!(expr("[]"), "[]");
+    assert_eq!(expr("[1]"), "[1]");
+    assert_eq!(expr("[1; 2; 3]"), "[1 2 3]");
+    assert_eq!(expr("[a + 1; f b]"), "[(+ a 1) (f b)]");
+    assert_eq!(expr("[[1]; []]"), "[[1] []]");
+}
+
+#[test]
+fn cons_is_right_associative() {
+    assert_eq!(expr("x :: xs"), "(:: x xs)");
+    assert_eq!(expr("1 :: 2 :: []"), "(:: 1 (:: 2 []))");
+    assert_eq!(expr("(xs :: []) :: []"), "(:: (:: xs []) [])");
+}
+
+#[test]
+fn cons_binds_looser_than_operations() {
+    assert_eq!(expr("a + 1 :: xs"), "(:: (+ a 1) xs)");
+    assert_eq!(expr("f x :: g y"), "(:: (f x) (g y))");
+    assert_eq!(expr("x :: xs @ ys"), "(:: x (@ xs ys))");
+}
+
+#[test]
+fn cons_binds_tighter_than_pipelines() {
+    assert_eq!(expr("x :: xs |> f"), "(f (:: x xs))");
+    assert_eq!(expr("xs |> map f |> length"), "(length (map f xs))");
+    assert_eq!(expr("x :: xs |> f >> g"), "(apply (fn [$x] (g (f $x))) (:: x xs))");
+}
+
+#[test]
+fn append() {
+    assert_eq!(expr("xs @ ys"), "(@ xs ys)");
+    assert_eq!(expr("xs @ ys @ zs"), "(@ (@ xs ys) zs)");
+    assert_eq!(expr("[1] @ [2; 3]"), "(@ [1] [2 3])");
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use syntax::diagnostics::Diagnostic;
+use syntax::lexer;
+use syntax::lexer::token::Token;
+
+use common::parse_with_errors;
+
+fn tokens(source: &str) -> Vec<Token> {
+    lexer::lexer().src_iter(source).map(|(_, token)| token).collect()
+}
+
+/// The only token of the source.
+fn token(source: &str) -> Token {
+    let mut tokens = tokens(source);
+    assert_eq!(tokens.len(), 1, "Expected a single token in {:?}, found {:?}", source, tokens);
+    tokens.remove(0)
+}
+
+fn string(value: &str) -> Token {
+    Token::DoubleQuote(value.to_string())
+}
+
+fn invalid(message: &str) -> Token {
+    Token::Invalid(message.to_string())
+}
+
+#[test]
+fn strings() {
+    assert_eq!(token(r#""""#), string(""));
+    assert_eq!(token(r#""hello world""#), string("hello world"));
+    assert_eq!(token(r#""wörld 😀""#), string("wörld 😀"));
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(token(r#""a\nb""#), string("a\nb"));
+    assert_eq!(token(r#""a\rb""#), string("a\rb"));
+    assert_eq!(token(r#""a\tb""#), string("a\tb"));
+    assert_eq!(token(r#""a\\b""#), string("a\\b"));
+    assert_eq!(token(r#""a\'b""#), string("a'b"));
+    assert_eq!(token(r#""a\"b""#), string("a\"b"));
+    assert_eq!(token(r#""\\""#), string("\\"));
+    assert_eq!(token(r#""\u{48}i""#), string("Hi"));
+    assert_eq!(token(r#""\u{e9}""#), string("é"));
+    assert_eq!(token(r#""\u{1F600}""#), string("😀"));
+    assert_eq!(token(r#""\u{10FFFF}""#), string("\u{10FFFF}"));
+}
+
+#[test]
+fn bad_string_escapes() {
+    assert_eq!(token(r#""\q""#), invalid("Unknown escape sequence `\\q`"));
+    assert_eq!(token(r#""\x41""#), invalid("Unknown escape sequence `\\x`"));
+    assert_eq!(token(r#""\u41""#), invalid("Expected `{` after `\\u`"));
+    assert_eq!(token(r#""\u{41""#), invalid("Unfinished unicode escape, expected `}`"));
+    assert_eq!(token(r#""\u{}""#), invalid("Invalid unicode escape `\\u{}`"));
+    assert_eq!(token(r#""\u{1234567}""#), invalid("Invalid unicode escape `\\u{1234567}`"));
+    assert_eq!(token(r#""\u{110000}""#), invalid("Invalid unicode escape `\\u{110000}`"));
+    assert_eq!(token(r#""\u{D800}""#), invalid("Invalid unicode escape `\\u{D800}`"));
+    assert_eq!(token(r#""\u{zz}""#), invalid("Invalid unicode escape `\\u{zz}`"));
+}
+
+#[test]
+fn strings_cannot_contain_nul() {
+    let message = "Strings cannot contain NUL characters";
+    assert_eq!(token(r#""a\0b""#), invalid(message));
+    assert_eq!(token(r#""\u{0}""#), invalid(message));
+    assert_eq!(token("\"a\0b\""), invalid(message));
+}
+
+#[test]
+fn unterminated_strings() {
+    let message = "Unterminated string literal";
+    assert_eq!(token(r#""abc"#), invalid(message));
+    assert_eq!(tokens("\"abc\n\"")[0], invalid(message));
+    // The escaped quote does not end the literal.
+    assert_eq!(tokens(r#""abc\""#)[0], invalid(message));
+}
+
+#[test]
+fn malformed_literals_are_reported() {
+    let (_, errors) = parse_with_errors("let a = \"abc\nlet b = \"\\q\"\nlet c = \"ok\"\n");
+    let messages = errors
+        .into_iter()
+        .map(|err| Diagnostic::from(err).message)
+        .collect::<Vec<_>>();
+
+    assert_eq!(messages, ["Unterminated string literal", "Unknown escape sequence `\\q`"]);
+}
+
+#[test]
+fn chars() {
+    assert_eq!(token("'a'"), Token::SingleQuote('a'));
+    assert_eq!(token("'é'"), Token::SingleQuote('é'));
+    assert_eq!(token("'😀'"), Token::SingleQuote('😀'));
+    assert_eq!(token("'\"'"), Token::SingleQuote('"'));
+}
+
+#[test]
+fn char_escapes() {
+    assert_eq!(token(r"'\n'"), Token::SingleQuote('\n'));
+    assert_eq!(token(r"'\r'"), Token::SingleQuote('\r'));
+    assert_eq!(token(r"'\t'"), Token::SingleQuote('\t'));
+    assert_eq!(token(r"'\0'"), Token::SingleQuote('\0'));
+    assert_eq!(token(r"'\\'"), Token::SingleQuote('\\'));
+    assert_eq!(token(r"'\''"), Token::SingleQuote('\''));
+    assert_eq!(token(r#"'\"'"#), Token::SingleQuote('"'));
+    assert_eq!(token(r"'\u{41}'"), Token::SingleQuote('A'));
+    assert_eq!(token(r"'\u{1F600}'"), Token::SingleQuote('😀'));
+}
+
+#[test]
+fn chars_hold_one_scalar_value() {
+    assert_eq!(token("''"), invalid("Empty character literal"));
+    assert_eq!(
+        token("'ab'"),
+        invalid("Character literals must contain exactly one character, found \"ab\""));
+    assert_eq!(
+        token(r"'\n\t'"),
+        invalid("Character literals must contain exactly one character, found \"\\n\\t\""));
+    // An `e` followed by a combining acute accent is two scalar values.
+    assert_eq!(
+        token("'e\u{301}'"),
+        invalid("Character literals must contain exactly one character, found \"e\\u{301}\""));
+}
+
+#[test]
+fn bad_char_escapes() {
+    assert_eq!(token(r"'\q'"), invalid("Unknown escape sequence `\\q`"));
+    assert_eq!(token(r"'\u{}'"), invalid("Invalid unicode escape `\\u{}`"));
+    assert_eq!(token(r"'\u{110000}'"), invalid("Invalid unicode escape `\\u{110000}`"));
+    assert_eq!(token(r"'\u{DFFF}'"), invalid("Invalid unicode escape `\\u{DFFF}`"));
+}
+
+#[test]
+fn unterminated_chars() {
+    let message = "Unterminated character literal";
+    assert_eq!(token("'a"), invalid(message));
+    assert_eq!(tokens("'a\n'")[0], invalid(message));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn arms_on_the_same_line() {
+    assert_eq!(expr("match x with 0 -> a | _ -> b"), "(match x (0 -> a) (_ -> b))");
+    assert_eq!(expr("match x with | 0 -> a | _ -> b"), "(match x (0 -> a) (_ -> b))");
+}
+
+#[test]
+fn arms_on_their_own_lines() {
+    let source = "\
+let f x =
+    match x with
+    | [] -> 0
+    | h :: _ -> h
+";
+    assert_eq!(item(source), "(let f [x] (block (match x ([] -> 0) ((:: h _) -> h))))");
+
+    let source = "\
+let f x =
+    match x with
+        | true -> 1
+        | false -> 0
+";
+    assert_eq!(item(source), "(let f [x] (block (match x (true -> 1) (false -> 0))))");
+}
+
+#[test]
+fn arm_bodies_are_expressions() {
+    assert_eq!(
+        expr("match x with | 1 -> a + 1 |> f | _ -> fn y -> y"),
+        "(match x (1 -> (f (+ a 1))) (_ -> (fn [y] y)))");
+}
+
+#[test]
+fn literal_patterns() {
+    assert_eq!(
+        expr("match x with | 1 -> a | 2.5 -> a | 'c' -> a | \"s\" -> a | false -> a"),
+        "(match x (1 -> a) (2.5 -> a) ('c' -> a) (\"s\" -> a) (false -> a))");
+}
+
+#[test]
+fn compound_patterns() {
+    assert_eq!(expr("match x with | (a, _) -> a"), "(match x ((a, _) -> a))");
+    assert_eq!(expr("match x with | [a; b] -> a"), "(match x ([a; b] -> a))");
+    assert_eq!(expr("match x with | a :: b :: rest -> a"), "(match x ((:: a (:: b rest)) -> a))");
+    assert_eq!(expr("match x with | () -> a"), "(match x (() -> a))");
+}
+
+#[test]
+fn constructor_patterns() {
+    assert_eq!(expr("match s with | Rect w h -> w"), "(match s ((Rect w h) -> w))");
+    assert_eq!(expr("match s with | Empty -> 0"), "(match s (Empty -> 0))");
+    assert_eq!(
+        expr("match s with | Node (Leaf, 1) [x] -> x"),
+        "(match s ((Node (Leaf, 1) [x]) -> x))");
+    assert_eq!(expr("match s with | Some x :: _ -> x"), "(match s ((:: (Some x) _) -> x))");
+}
+
+#[test]
+fn negative_literal_patterns() {
+    assert_eq!(
+        expr("match x with | -1 -> a | -2.5 -> a | _ -> b"),
+        "(match x (-1 -> a) (-2.5 -> a) (_ -> b))");
+}
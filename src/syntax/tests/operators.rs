@@ -0,0 +1,45 @@
+mod common;
+
+use common::{expr, parse_with_errors};
+
+#[test]
+fn minus_without_spaces_subtracts() {
+    assert_eq!(expr("n-1"), "(- n 1)");
+    assert_eq!(expr("n-1.5"), "(- n 1.5)");
+    assert_eq!(expr("f n-1"), "(- (f n) 1)");
+    assert_eq!(expr("3-2-1"), "(- (- 3 2) 1)");
+}
+
+#[test]
+fn negative_literals_are_negations() {
+    assert_eq!(expr("-1"), "(- 1)");
+    assert_eq!(expr("-2.5"), "(- 2.5)");
+    assert_eq!(expr("f (-1)"), "(f (- 1))");
+    assert_eq!(expr("-9223372036854775807 - 1"), "(- (- 9223372036854775807) 1)");
+
+    let (_, errors) = parse_with_errors("let x = -9223372036854775808\n");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+}
+
+#[test]
+fn unary_operators_bind_tighter_than_binary_ones() {
+    assert_eq!(expr("-a * b"), "(* (- a) b)");
+    assert_eq!(expr("-a + b"), "(+ (- a) b)");
+    assert_eq!(expr("a - -b"), "(- a (- b))");
+    assert_eq!(expr("a * -b"), "(* a (- b))");
+    assert_eq!(expr("-f x"), "(- (f x))");
+    assert_eq!(expr("!a and b"), "(and (! a) b)");
+    assert_eq!(expr("!a == b"), "(== (! a) b)");
+    assert_eq!(expr("a or !b"), "(or a (! b))");
+    assert_eq!(expr("!(a and b)"), "(! (and a b))");
+}
+
+#[test]
+fn division_and_remainder_bind_like_multiplication() {
+    assert_eq!(expr("a + b / c"), "(+ a (/ b c))");
+    assert_eq!(expr("a - b % c"), "(- a (% b c))");
+    assert_eq!(expr("a / b * c"), "(* (/ a b) c)");
+    assert_eq!(expr("a % b / c"), "(/ (% a b) c)");
+    assert_eq!(expr("a * b % c"), "(% (* a b) c)");
+    assert_eq!(expr("(a + b) / c"), "(/ (+ a b) c)");
+}
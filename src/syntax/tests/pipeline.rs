@@ -0,0 +1,67 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn pipeline_applies_the_right_side() {
+    assert_eq!(expr("x |> f"), "(f x)");
+    assert_eq!(expr("x |> f |> g"), "(g (f x))");
+}
+
+#[test]
+fn pipeline_appends_to_the_arguments() {
+    assert_eq!(expr("x |> f a"), "(f a x)");
+    assert_eq!(expr("x |> (fn y -> y)"), "(apply (fn [y] y) x)");
+}
+
+#[test]
+fn pipeline_binds_loosest() {
+    assert_eq!(expr("a + 1 |> f"), "(f (+ a 1))");
+    assert_eq!(expr("a or b |> f"), "(f (or a b))");
+}
+
+#[test]
+fn composition() {
+    assert_eq!(expr("f >> g"), "(fn [$x] (g (f $x)))");
+    assert_eq!(expr("(fn y -> y) >> g"), "(fn [$x] (g (apply (fn [y] y) $x)))");
+}
+
+#[test]
+fn composition_evaluates_its_operands_once() {
+    assert_eq!(expr("f a >> g"), "(block (let $f (f a)) (fn [$x] (g ($f $x))))");
+    assert_eq!(
+        expr("f >> g b >> h"),
+        "(block (let $f (block (let $g (g b)) (fn [$x] ($g (f $x))))) (fn [$x] (h ($f $x))))");
+}
+
+#[test]
+fn composition_binds_tighter_than_pipeline() {
+    assert_eq!(expr("x |> f >> g"), "(apply (fn [$x] (g (f $x))) x)");
+}
+
+#[test]
+fn pipelines_continue_on_the_next_line() {
+    let source = "\
+let f xs =
+    xs
+    |> map g
+    |> sum
+";
+    assert_eq!(item(source), "(let f [xs] (block (sum (map g xs))))");
+
+    let source = "\
+let f xs =
+    xs
+        |> map g
+        |> sum
+";
+    assert_eq!(item(source), "(let f [xs] (block (sum (map g xs))))");
+
+    let source = "\
+let f xs =
+    let ys = xs
+        |> map g
+    ys |> sum
+";
+    assert_eq!(item(source), "(let f [xs] (block (let ys (map g xs)) (sum ys)))");
+}
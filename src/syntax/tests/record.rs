@@ -0,0 +1,32 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn record_declaration() {
+    assert_eq!(item("type Point = { x: float; y: float }\n"), "(type Point {x: float; y: float})");
+    assert_eq!(
+        item("type Named = { name: string; tags: string list; pos: int * int }\n"),
+        "(type Named {name: string; tags: (string list); pos: (int * int)})");
+    assert_eq!(item("type Handler = { run: int -> bool }\n"), "(type Handler {run: (int -> bool)})");
+}
+
+#[test]
+fn record_construction() {
+    assert_eq!(expr("{ x = 1.0; y = 2.0 }"), "{x = 1.0; y = 2.0}");
+    assert_eq!(expr("{ x = f a; y = a + 1.0 }"), "{x = (f a); y = (+ a 1.0)}");
+}
+
+#[test]
+fn field_access() {
+    assert_eq!(expr("p.x"), "(. p x)");
+    assert_eq!(expr("p.a.b"), "(. (. p a) b)");
+    assert_eq!(expr("f p.x"), "(f (. p x))");
+    assert_eq!(expr("(f p).x + 1.0"), "(+ (. (f p) x) 1.0)");
+}
+
+#[test]
+fn record_update() {
+    assert_eq!(expr("{ p with x = 3.0 }"), "{p with x = 3.0}");
+    assert_eq!(expr("{ p with x = 1.0; y = p.x }"), "{p with x = 1.0; y = (. p x)}");
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use syntax::diagnostics::Diagnostic;
+use syntax::parser::SyntaxError;
+
+use common::{parse_with_errors, show};
+
+/// The items as s-expressions and the messages of the errors.
+fn recover(source: &str) -> (Vec<String>, Vec<String>) {
+    let (nodes, errors) = parse_with_errors(source);
+
+    let nodes = nodes.iter().map(show).collect();
+    let errors = errors
+        .into_iter()
+        .map(|err| Diagnostic::from(err).message)
+        .collect();
+
+    (nodes, errors)
+}
+
+#[test]
+fn every_item_with_an_error_is_reported() {
+    let source = "\
+let a = 1 +
+let b = 2
+let c = )
+let d = 4
+";
+
+    let (nodes, errors) = recover(source);
+
+    assert_eq!(nodes, ["error", "(let b 2)", "error", "(let d 4)"]);
+    assert_eq!(errors, ["Unexpected end of the item", "Unexpected token RParen"]);
+}
+
+#[test]
+fn errors_in_blocks_lose_the_whole_item() {
+    let source = "\
+let f x =
+    let y = x +
+    y
+let g = 1
+";
+
+    let (nodes, errors) = recover(source);
+
+    assert_eq!(nodes, ["error", "(let g 1)"]);
+    assert_eq!(errors, ["Unexpected new line"]);
+}
+
+#[test]
+fn let_later_on_a_line_does_not_start_an_item() {
+    let (nodes, errors) = recover("let f x = let y = 1\nlet g = 2\n");
+
+    assert_eq!(nodes, ["error", "(let g 2)"]);
+    assert_eq!(errors, ["Unexpected token KwLet"]);
+}
+
+#[test]
+fn incomplete_items_point_after_their_last_token() {
+    let source = "let a = (1 +\nlet b = 2\n";
+    let (_, errors) = parse_with_errors(source);
+
+    match &errors[..] {
+        [SyntaxError::Incomplete(span)] => assert_eq!(span.lo, source.find('+').unwrap() + 1),
+        errors => panic!("Expected an incomplete item, found {:?}", errors),
+    }
+}
+
+#[test]
+fn error_nodes_cover_their_item() {
+    let source = "let a = )\nlet b = 2\n";
+    let (nodes, _) = parse_with_errors(source);
+
+    assert_eq!((nodes[0].span.lo, nodes[0].span.hi), (0, source.find('\n').unwrap()));
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn tuple() {
+    assert_eq!(expr("(1, 2)"), "(tuple 1 2)");
+    assert_eq!(expr("(1, \"two\", 3.0)"), "(tuple 1 \"two\" 3.0)");
+    assert_eq!(expr("(a + 1, f b)"), "(tuple (+ a 1) (f b))");
+}
+
+#[test]
+fn unit_is_the_empty_tuple() {
+    assert_eq!(expr("()"), "(tuple)");
+    assert_eq!(expr("f ()"), "(f (tuple))");
+}
+
+#[test]
+fn parens_without_comma_group() {
+    assert_eq!(expr("(1)"), "1");
+    assert_eq!(expr("(a + b) * c"), "(* (+ a b) c)");
+}
+
+#[test]
+fn nested_tuples() {
+    assert_eq!(expr("((1, 2), 3)"), "(tuple (tuple 1 2) 3)");
+}
+
+#[test]
+fn destructuring() {
+    assert_eq!(item("let (a, b) = t\n"), "(let (a, b) t)");
+    assert_eq!(item("let (a, (b, _)) = t\n"), "(let (a, (b, _)) t)");
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use common::{expr, item};
+
+#[test]
+fn union_declaration() {
+    assert_eq!(
+        item("type Shape = | Circle of float | Rect of float * float | Empty\n"),
+        "(type Shape | Circle of float | Rect of float * float | Empty)");
+    assert_eq!(
+        item("type Tree = | Leaf | Node of Tree * int list\n"),
+        "(type Tree | Leaf | Node of Tree * (int list))");
+}
+
+#[test]
+fn union_cases_on_their_own_lines() {
+    let source = "\
+type Shape =
+    | Circle of float
+    | Rect of float * float
+    | Empty
+";
+
+    assert_eq!(item(source), "(type Shape | Circle of float | Rect of float * float | Empty)");
+}
+
+#[test]
+fn constructors_are_calls() {
+    assert_eq!(expr("Rect 1.0 2.0"), "(Rect 1.0 2.0)");
+    assert_eq!(expr("Empty"), "Empty");
+    assert_eq!(expr("map (Rect 1.0) xs"), "(map (Rect 1.0) xs)");
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use syntax::diagnostics::Diagnostic;
+
+use common::{parse_with_errors, show};
+
+/// The items as s-expressions and the errors.
+fn parse(source: &str) -> (Vec<String>, Vec<Diagnostic>) {
+    let (nodes, errors) = parse_with_errors(source);
+    (nodes.iter().map(show).collect(), errors.into_iter().map(Diagnostic::from).collect())
+}
+
+fn messages(errors: &[Diagnostic]) -> Vec<&str> {
+    errors.iter().map(|err| err.message.as_str()).collect()
+}
+
+#[test]
+fn unknown_characters_are_reported_first() {
+    let (_, errors) = parse("let a = )\nlet b = x # comment\n");
+    assert_eq!(messages(&errors), ["Unknown token `#`", "Unexpected token RParen"]);
+    assert_eq!(errors[0].notes, ["comments start with `//`"]);
+}
+
+#[test]
+fn adjacent_unknown_characters_are_merged() {
+    let (nodes, errors) = parse("let c = a && b\n");
+    assert_eq!(messages(&errors), ["Unknown token `&&`"]);
+    assert_eq!(errors[0].notes, ["use `and`"]);
+    assert_eq!(nodes, ["(let c (a b))"]);
+}
+
+#[test]
+fn hints_do_not_guess() {
+    let (_, errors) = parse("let s = a ^ b\n");
+    assert_eq!(errors[0].notes, ["`^` is not an operator, use `+` to concatenate strings"]);
+
+    let (_, errors) = parse("let s = a ? b\n");
+    assert_eq!(messages(&errors), ["Unknown token `?`"]);
+    assert!(errors[0].notes.is_empty());
+}
+
+#[test]
+fn items_with_unknown_characters_are_still_parsed() {
+    let (nodes, errors) = parse("let d = a ~ b + )\nlet e = 1\n");
+    assert_eq!(messages(&errors), ["Unknown token `~`", "Unexpected token RParen"]);
+    assert_eq!(nodes, ["error", "(let e 1)"]);
+
+    let (nodes, errors) = parse("#\nlet a = 1\n");
+    assert_eq!(messages(&errors), ["Unknown token `#`"]);
+    assert_eq!(nodes, ["(let a 1)"]);
+}
+
+#[test]
+fn items_with_malformed_tokens_are_not_checked() {
+    let (nodes, errors) = parse("let a = 99999999999999999999 + )\nlet b = 1\n");
+    assert_eq!(messages(&errors), ["Integer literals must fit in 64 bits"]);
+    assert_eq!(errors[0].notes, ["the rest of the item is not checked"]);
+    assert_eq!(nodes, ["error", "(let b 1)"]);
+}